@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+// Local stand-in for the Pyth receiver program. Deployed at the receiver's address
+// on localnet, it posts PriceUpdateV2 accounts in the receiver's layout without
+// Wormhole verification, so tests can set collateral prices directly.
+declare_id!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+#[program]
+pub mod mock_pyth_receiver {
+    use super::*;
+    
+    // Create or overwrite the fully verified price update of `message.feed_id`
+    pub fn post_price(ctx: Context<PostPrice>, message: PriceFeedMessage) -> Result<()> {
+        let price_update = &mut ctx.accounts.price_update;
+        price_update.write_authority = ctx.accounts.payer.key();
+        price_update.verification_level = VerificationLevel::Full;
+        price_update.price_message = message;
+        price_update.posted_slot = Clock::get()?.slot;
+        
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(message: PriceFeedMessage)]
+pub struct PostPrice<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PriceUpdateV2::LEN,
+        seeds = [b"price_update", message.feed_id.as_ref()],
+        bump
+    )]
+    pub price_update: Account<'info, PriceUpdateV2>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+// Same name, and so the same discriminator, as the receiver's account
+#[account]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,    // 32 bytes
+    pub verification_level: VerificationLevel, // 2 bytes
+    pub price_message: PriceFeedMessage, // 84 bytes
+    pub posted_slot: u64,           // 8 bytes
+}
+
+impl PriceUpdateV2 {
+    pub const LEN: usize = 32 + 2 + 84 + 8;
+}
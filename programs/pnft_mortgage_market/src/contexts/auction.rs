@@ -1,9 +1,13 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount, Transfer};
+use anchor_spl::token::{Token, TokenAccount, Mint, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
 
 use crate::states::*;
 use crate::errors::*;
 use crate::events::*;
+use crate::utils::checked_sub;
+use crate::contexts::pool::record_pool_write_off;
+use crate::pnft::*;
 
 #[derive(Accounts)]
 pub struct PlaceBid<'info> {
@@ -25,49 +29,94 @@ pub struct PlaceBid<'info> {
     // USDC token accounts
     #[account(
         mut,
+        constraint = bidder_usdc.mint == loan.loan_mint,
         constraint = bidder_usdc.owner == bidder.key()
     )]
     pub bidder_usdc: Account<'info, TokenAccount>,
     
+    // Bid escrow owned by the auction PDA
     #[account(
         mut,
-        constraint = treasury_usdc.owner == treasury.key()
+        seeds = [
+            b"auction_escrow",
+            loan.key().as_ref()
+        ],
+        bump
     )]
-    pub treasury_usdc: Account<'info, TokenAccount>,
+    pub auction_usdc: Account<'info, TokenAccount>,
     
-    /// CHECK: Treasury account
-    #[account(mut)]
-    pub treasury: UncheckedAccount<'info>,
+    // Refund destination for the outbid top bid, required once a bid exists
+    #[account(
+        mut,
+        constraint = previous_bidder_usdc.mint == loan.loan_mint,
+        constraint = previous_bidder_usdc.owner == auction.current_bidder @ LoanError::InvalidBidRefundAccount
+    )]
+    pub previous_bidder_usdc: Option<Account<'info, TokenAccount>>,
     
     pub token_program: Program<'info, Token>,
 }
 
 pub fn place_bid_handler(ctx: Context<PlaceBid>, bid_amount: u64) -> Result<()> {
-    let auction = &mut ctx.accounts.auction;
+    let auction = &ctx.accounts.auction;
     let clock = Clock::get()?;
     
-    // Check if auction is still active
-    require!(auction.is_active(clock.unix_timestamp), LoanError::AuctionStillActive);
+    require!(auction.mode == AuctionMode::English, LoanError::InvalidAuctionMode);
+    require!(auction.is_active(clock.unix_timestamp), LoanError::AuctionEnded);
     
-    // Check if bid is higher than current bid
-    require!(bid_amount > auction.current_bid, LoanError::BidTooLow);
+    // First bid opens at the starting price, later bids must clear the minimum increment
+    require!(bid_amount >= auction.min_bid()?, LoanError::BidTooLow);
     
-    // Transfer USDC from bidder to treasury
+    // Escrow the bid until the auction settles or the bidder is outbid
     let transfer_ctx = CpiContext::new(
         ctx.accounts.token_program.to_account_info(),
         Transfer {
             from: ctx.accounts.bidder_usdc.to_account_info(),
-            to: ctx.accounts.treasury_usdc.to_account_info(),
+            to: ctx.accounts.auction_usdc.to_account_info(),
             authority: ctx.accounts.bidder.to_account_info(),
         },
     );
     
     anchor_spl::token::transfer(transfer_ctx, bid_amount)?;
     
+    // Refund the previous top bid out of escrow
+    if auction.current_bid > 0 {
+        let previous_bidder_usdc = ctx.accounts.previous_bidder_usdc.as_ref()
+            .ok_or(LoanError::InvalidBidRefundAccount)?;
+        let auction_seeds: &[&[&[u8]]] = &[&[b"auction", auction.loan.as_ref(), &[auction.bump]]];
+        
+        let refund_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.auction_usdc.to_account_info(),
+                to: previous_bidder_usdc.to_account_info(),
+                authority: ctx.accounts.auction.to_account_info(),
+            },
+            auction_seeds,
+        );
+        
+        anchor_spl::token::transfer(refund_ctx, auction.current_bid)?;
+        
+        emit!(BidRefunded {
+            auction: auction.key(),
+            bidder: auction.current_bidder,
+            amount: auction.current_bid,
+        });
+    }
+    
     // Update auction
+    let auction = &mut ctx.accounts.auction;
     auction.current_bid = bid_amount;
     auction.current_bidder = ctx.accounts.bidder.key();
     
+    // Late bids push the end out so others can respond
+    if auction.extend_for_bid(clock.unix_timestamp)? {
+        emit!(AuctionExtended {
+            auction: auction.key(),
+            bidder: auction.current_bidder,
+            end_time: auction.end_time,
+        });
+    }
+    
     emit!(BidPlaced {
         auction: auction.key(),
         bidder: ctx.accounts.bidder.key(),
@@ -94,12 +143,28 @@ pub struct SettleAuction<'info> {
     )]
     pub loan: Account<'info, Loan>,
     
+    // Auction terms for relisting a pool loan below its reserve
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Box<Account<'info, Protocol>>,
+    
     #[account(
         mut,
         constraint = vault.loan == loan.key()
     )]
     pub vault: Account<'info, Vault>,
     
+    // pNFT accounts
+    #[account(address = auction.collateral_mint)]
+    pub collateral_mint: Account<'info, Mint>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
     #[account(
         mut,
         constraint = vault_token.mint == auction.collateral_mint,
@@ -107,47 +172,368 @@ pub struct SettleAuction<'info> {
     )]
     pub vault_token: Account<'info, TokenAccount>,
     
+    // Winning bidder, or the lender when the reserve price is not met;
+    // not needed when a pool loan is relisted
+    #[account(
+        mut,
+        constraint = recipient_token.mint == auction.collateral_mint,
+        constraint = recipient_token.owner == recipient.key()
+    )]
+    pub recipient_token: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Collateral recipient, owner of recipient_token
+    #[account(address = auction.collateral_recipient(loan.lender))]
+    pub recipient: UncheckedAccount<'info>,
+    
+    // Proceeds: escrowed winning bid paid to the lender up to the debt, surplus to the borrower
+    #[account(
+        mut,
+        seeds = [
+            b"auction_escrow",
+            loan.key().as_ref()
+        ],
+        bump
+    )]
+    pub auction_usdc: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = lender_usdc.mint == loan.loan_mint,
+        constraint = lender_usdc.owner == loan.lender
+    )]
+    pub lender_usdc: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = borrower_usdc.mint == loan.loan_mint,
+        constraint = borrower_usdc.owner == loan.borrower
+    )]
+    pub borrower_usdc: Box<Account<'info, TokenAccount>>,
+    
+    // Refund destination for a top bid below the reserve price
+    #[account(
+        mut,
+        constraint = bidder_usdc.mint == loan.loan_mint,
+        constraint = bidder_usdc.owner == auction.current_bidder @ LoanError::InvalidBidRefundAccount
+    )]
+    pub bidder_usdc: Option<Box<Account<'info, TokenAccount>>>,
+    
+    // Lending pool that funded the loan, required when loan.pool_funded
     #[account(
         mut,
-        constraint = winner_token.mint == auction.collateral_mint,
-        constraint = winner_token.owner == auction.current_bidder
+        constraint = pool.key() == loan.lender @ LoanError::InvalidPool
     )]
-    pub winner_token: Account<'info, TokenAccount>,
+    pub pool: Option<Account<'info, LendingPool>>,
+    
+    /// CHECK: Token record of vault_token, validated by Token Metadata
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token record of recipient_token, validated by Token Metadata
+    #[account(mut)]
+    pub recipient_token_record: Option<UncheckedAccount<'info>>,
     
+    pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 pub fn settle_handler(ctx: Context<SettleAuction>) -> Result<()> {
-    let auction = &mut ctx.accounts.auction;
-    let loan = &mut ctx.accounts.loan;
     let clock = Clock::get()?;
     
     // Check if auction has ended
-    require!(!auction.is_active(clock.unix_timestamp), LoanError::AuctionStillActive);
+    require!(!ctx.accounts.auction.is_active(clock.unix_timestamp), LoanError::AuctionStillActive);
     
-    // Transfer NFT to winner
-    let transfer_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Transfer {
-            from: ctx.accounts.vault_token.to_account_info(),
-            to: ctx.accounts.winner_token.to_account_info(),
-            authority: ctx.accounts.vault.to_account_info(),
-        },
-    );
+    // A pool has no way to sell a pNFT sent to its PDA, so a pool loan below the
+    // reserve is relisted without one instead of being handed to the pool
+    if ctx.accounts.loan.pool_funded && !ctx.accounts.auction.meets_reserve() {
+        return relist_auction(ctx, clock.unix_timestamp);
+    }
+    
+    // Transfer pNFT to the winner, or back to the lender below the reserve price
+    let loan_key = ctx.accounts.loan.key();
+    let vault_seeds: &[&[&[u8]]] = &[&[b"vault", loan_key.as_ref(), &[ctx.accounts.vault.bump]]];
+    let recipient_token = ctx.accounts.recipient_token.as_ref()
+        .ok_or(LoanError::MissingCollateralAccount)?;
+    
+    ctx.accounts.pnft.transfer(
+        &ctx.accounts.collateral_mint,
+        PnftHolder::new(ctx.accounts.vault_token.to_account_info(), ctx.accounts.vault.to_account_info(), &ctx.accounts.vault_token_record),
+        PnftHolder::new(recipient_token.to_account_info(), ctx.accounts.recipient.to_account_info(), &ctx.accounts.recipient_token_record),
+        ctx.accounts.vault.to_account_info(),
+        ctx.accounts.caller.to_account_info(),
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.associated_token_program,
+    )
+    .invoke_signed(vault_seeds)?;
     
-    anchor_spl::token::transfer(transfer_ctx, 1)?;
+    // Interest runs until settlement; a winning bid pays down the debt first,
+    // an unsuccessful top bid is refunded in full
+    ctx.accounts.loan.accrue_interest(clock.unix_timestamp)?;
+    let auction = &ctx.accounts.auction;
+    let sold = auction.meets_reserve();
+    let (lender_amount, borrower_amount, refund_amount) = if sold {
+        let (lender_amount, borrower_amount) = split_proceeds(auction.current_bid, &ctx.accounts.loan)?;
+        (lender_amount, borrower_amount, 0)
+    } else {
+        (0, 0, auction.current_bid)
+    };
+    let auction_seeds: &[&[&[u8]]] = &[&[b"auction", loan_key.as_ref(), &[auction.bump]]];
+    
+    let bidder_usdc = match refund_amount {
+        0 => None,
+        _ => Some(ctx.accounts.bidder_usdc.as_ref().ok_or(LoanError::InvalidBidRefundAccount)?),
+    };
+    
+    for (destination, amount) in [
+        (Some(&ctx.accounts.lender_usdc), lender_amount),
+        (Some(&ctx.accounts.borrower_usdc), borrower_amount),
+        (bidder_usdc, refund_amount),
+    ] {
+        let Some(destination) = destination.filter(|_| amount > 0) else {
+            continue;
+        };
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.auction_usdc.to_account_info(),
+                to: destination.to_account_info(),
+                authority: ctx.accounts.auction.to_account_info(),
+            },
+            auction_seeds,
+        );
+        anchor_spl::token::transfer(transfer_ctx, amount)?;
+    }
+    
+    let auction = &mut ctx.accounts.auction;
+    let loan = &mut ctx.accounts.loan;
     
     // Update auction status
     auction.status = AuctionStatus::Settled;
+    close_out_loan(loan, ctx.accounts.pool.as_mut(), &ctx.accounts.lender_usdc.key())?;
     
-    // Update loan status
-    loan.status = LoanStatus::Liquidated;
+    if sold {
+        emit!(AuctionSettled {
+            auction: auction.key(),
+            winner: auction.current_bidder,
+            winning_bid: auction.current_bid,
+            lender_amount,
+            borrower_amount,
+        });
+    } else {
+        emit!(AuctionReserveNotMet {
+            auction: auction.key(),
+            lender: loan.lender,
+            highest_bid: auction.current_bid,
+            reserve_price: auction.reserve_price,
+        });
+    }
+    
+    Ok(())
+}
+
+// Refund the top bid below the reserve and start a new round on the protocol's
+// auction terms; the collateral stays in the vault and the loan stays InAuction
+fn relist_auction(ctx: Context<SettleAuction>, current_time: i64) -> Result<()> {
+    let auction = &ctx.accounts.auction;
+    let highest_bid = auction.current_bid;
+    
+    if highest_bid > 0 {
+        let bidder_usdc = ctx.accounts.bidder_usdc.as_ref()
+            .ok_or(LoanError::InvalidBidRefundAccount)?;
+        let loan_key = ctx.accounts.loan.key();
+        let auction_seeds: &[&[&[u8]]] = &[&[b"auction", loan_key.as_ref(), &[auction.bump]]];
+        let refund_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.auction_usdc.to_account_info(),
+                to: bidder_usdc.to_account_info(),
+                authority: auction.to_account_info(),
+            },
+            auction_seeds,
+        );
+        anchor_spl::token::transfer(refund_ctx, highest_bid)?;
+    }
+    
+    let config = &ctx.accounts.protocol.auction_config;
+    let auction = &mut ctx.accounts.auction;
+    auction.relist(current_time, config.duration, config.max_extension)?;
+    
+    emit!(AuctionRelisted {
+        auction: auction.key(),
+        loan: auction.loan,
+        highest_bid,
+        end_time: auction.end_time,
+    });
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct BuyNow<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = auction.status == AuctionStatus::Active
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+    
+    #[account(
+        mut,
+        constraint = loan.key() == auction.loan
+    )]
+    pub loan: Box<Account<'info, Loan>>,
+    
+    #[account(
+        mut,
+        constraint = vault.loan == loan.key()
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+    
+    // pNFT accounts
+    #[account(address = auction.collateral_mint)]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
+    #[account(
+        mut,
+        constraint = vault_token.mint == auction.collateral_mint,
+        constraint = vault_token.owner == vault.key()
+    )]
+    pub vault_token: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = buyer_token.mint == auction.collateral_mint,
+        constraint = buyer_token.owner == buyer.key()
+    )]
+    pub buyer_token: Box<Account<'info, TokenAccount>>,
+    
+    // Proceeds: paid straight from the buyer to the lender up to the debt, surplus to the borrower
+    #[account(
+        mut,
+        constraint = buyer_usdc.mint == loan.loan_mint,
+        constraint = buyer_usdc.owner == buyer.key()
+    )]
+    pub buyer_usdc: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = lender_usdc.mint == loan.loan_mint,
+        constraint = lender_usdc.owner == loan.lender
+    )]
+    pub lender_usdc: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = borrower_usdc.mint == loan.loan_mint,
+        constraint = borrower_usdc.owner == loan.borrower
+    )]
+    pub borrower_usdc: Box<Account<'info, TokenAccount>>,
+    
+    // Lending pool that funded the loan, required when loan.pool_funded
+    #[account(
+        mut,
+        constraint = pool.key() == loan.lender @ LoanError::InvalidPool
+    )]
+    pub pool: Option<Box<Account<'info, LendingPool>>>,
+    
+    /// CHECK: Token record of vault_token, validated by Token Metadata
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token record of buyer_token, validated by Token Metadata
+    #[account(mut)]
+    pub buyer_token_record: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn buy_now_handler(ctx: Context<BuyNow>) -> Result<()> {
+    let clock = Clock::get()?;
+    let auction = &ctx.accounts.auction;
+    
+    require!(matches!(auction.mode, AuctionMode::Dutch { .. }), LoanError::InvalidAuctionMode);
+    require!(auction.is_active(clock.unix_timestamp), LoanError::AuctionEnded);
+    
+    // First buyer pays the current price on the decay curve
+    let price = auction.current_price(clock.unix_timestamp)?;
+    require!(price > 0 && price >= auction.reserve_price, LoanError::BidTooLow);
+    
+    ctx.accounts.loan.accrue_interest(clock.unix_timestamp)?;
+    let (lender_amount, borrower_amount) = split_proceeds(price, &ctx.accounts.loan)?;
+    
+    for (destination, amount) in [
+        (&ctx.accounts.lender_usdc, lender_amount),
+        (&ctx.accounts.borrower_usdc, borrower_amount),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.buyer_usdc.to_account_info(),
+                to: destination.to_account_info(),
+                authority: ctx.accounts.buyer.to_account_info(),
+            },
+        );
+        anchor_spl::token::transfer(transfer_ctx, amount)?;
+    }
+    
+    // Transfer pNFT to the buyer
+    let loan_key = ctx.accounts.loan.key();
+    let vault_seeds: &[&[&[u8]]] = &[&[b"vault", loan_key.as_ref(), &[ctx.accounts.vault.bump]]];
+    
+    ctx.accounts.pnft.transfer(
+        &ctx.accounts.collateral_mint,
+        PnftHolder::new(ctx.accounts.vault_token.to_account_info(), ctx.accounts.vault.to_account_info(), &ctx.accounts.vault_token_record),
+        PnftHolder::new(ctx.accounts.buyer_token.to_account_info(), ctx.accounts.buyer.to_account_info(), &ctx.accounts.buyer_token_record),
+        ctx.accounts.vault.to_account_info(),
+        ctx.accounts.buyer.to_account_info(),
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.associated_token_program,
+    )
+    .invoke_signed(vault_seeds)?;
+    
+    let auction = &mut ctx.accounts.auction;
+    auction.current_bid = price;
+    auction.current_bidder = ctx.accounts.buyer.key();
+    auction.status = AuctionStatus::Settled;
+    close_out_loan(&mut ctx.accounts.loan, ctx.accounts.pool.as_deref_mut(), &ctx.accounts.lender_usdc.key())?;
     
     emit!(AuctionSettled {
         auction: auction.key(),
         winner: auction.current_bidder,
-        winning_bid: auction.current_bid,
+        winning_bid: price,
+        lender_amount,
+        borrower_amount,
     });
     
     Ok(())
 }
+
+// Sale proceeds pay the lender up to the accrued debt; any surplus belongs to the borrower
+fn split_proceeds(amount: u64, loan: &Loan) -> Result<(u64, u64)> {
+    let lender_amount = amount.min(loan.outstanding_amount);
+    Ok((lender_amount, checked_sub(amount, lender_amount)?))
+}
+
+// The debt is closed out with whatever the auction recovered
+fn close_out_loan(loan: &mut Loan, pool: Option<&mut Account<LendingPool>>, lender_usdc: &Pubkey) -> Result<()> {
+    record_pool_write_off(loan, pool, lender_usdc)?;
+    loan.outstanding_amount = 0;
+    loan.accrued_interest = 0;
+    loan.status = LoanStatus::Liquidated;
+    Ok(())
+}
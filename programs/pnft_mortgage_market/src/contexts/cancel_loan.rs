@@ -0,0 +1,115 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::{CloseAccount, Token, TokenAccount, Transfer};
+
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::utils::checked_add;
+
+#[derive(Accounts)]
+pub struct CancelLoan<'info> {
+    // The principal was disbursed at create_loan, so only the borrower can
+    // unwind the loan by handing it back
+    #[account(
+        mut,
+        address = loan.borrower @ LoanError::UnauthorizedCancellation
+    )]
+    pub borrower: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = borrower,
+        constraint = loan.status == LoanStatus::Pending @ LoanError::LoanNotPending
+    )]
+    pub loan: Account<'info, Loan>,
+    
+    #[account(
+        mut,
+        constraint = borrower_usdc.mint == loan.loan_mint,
+        constraint = borrower_usdc.owner == borrower.key()
+    )]
+    pub borrower_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = lender_usdc.mint == loan.loan_mint,
+        constraint = lender_usdc.owner == loan.lender
+    )]
+    pub lender_usdc: Account<'info, TokenAccount>,
+    
+    // Vault of the loan, closed as well if create_vault already ran so the
+    // loan PDA can be originated again
+    /// CHECK: Vault PDA of the loan, may not be initialized
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            loan.key().as_ref()
+        ],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+    
+    /// CHECK: Collateral token account of the vault, may not exist
+    #[account(
+        mut,
+        address = get_associated_token_address(&vault.key(), &loan.collateral_mint)
+    )]
+    pub vault_token: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<CancelLoan>) -> Result<()> {
+    let loan = &ctx.accounts.loan;
+    let refunded_amount = loan.loan_amount;
+    
+    // Return the full principal to the lender; the origination fee stays with the treasury
+    let refund_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.borrower_usdc.to_account_info(),
+            to: ctx.accounts.lender_usdc.to_account_info(),
+            authority: ctx.accounts.borrower.to_account_info(),
+        },
+    );
+    anchor_spl::token::transfer(refund_ctx, refunded_amount)?;
+    
+    // The vault token account is still empty while the loan is pending;
+    // the vault closes it before closing itself
+    let loan_key = loan.key();
+    let vault_seeds: &[&[&[u8]]] = &[&[b"vault", loan_key.as_ref(), &[ctx.bumps.vault]]];
+    if ctx.accounts.vault_token.owner == &anchor_spl::token::ID {
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault_token.to_account_info(),
+                destination: ctx.accounts.borrower.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+            vault_seeds,
+        );
+        anchor_spl::token::close_account(close_ctx)?;
+    }
+    
+    let vault = ctx.accounts.vault.to_account_info();
+    if vault.owner == &crate::ID {
+        let borrower = ctx.accounts.borrower.to_account_info();
+        let lamports = checked_add(borrower.lamports(), vault.lamports())?;
+        **borrower.try_borrow_mut_lamports()? = lamports;
+        **vault.try_borrow_mut_lamports()? = 0;
+        vault.assign(&system_program::ID);
+        vault.realloc(0, false)?;
+    }
+    
+    emit!(LoanCancelled {
+        loan: loan.key(),
+        borrower: loan.borrower,
+        lender: loan.lender,
+        refunded_amount,
+    });
+    
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+
+use crate::states::*;
+use crate::errors::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CollectionConfigParams {
+    pub max_ltv: u16,               // basis points
+    pub liquidation_threshold: u16, // basis points
+    pub max_duration: i64,          // seconds
+    pub enabled: bool,
+    pub oracle_source: OracleSource,
+    pub price_feed: Pubkey,
+    pub pyth_feed_id: [u8; 32],
+    pub max_price_age: u32,         // seconds
+    pub max_confidence_bps: u16,
+    pub late_fee_policy: Option<LateFeePolicy>, // None uses the protocol default
+}
+
+impl CollectionConfigParams {
+    fn apply(&self, config: &mut CollectionConfig) -> Result<()> {
+        require!(
+            self.max_ltv > 0 && self.max_ltv <= 10000
+                && self.liquidation_threshold > 0
+                && self.max_duration > 0
+                && self.max_price_age > 0
+                && self.max_confidence_bps > 0 && self.max_confidence_bps <= 10000
+                && self.late_fee_policy.map_or(true, |policy| policy.is_valid()),
+            LoanError::InvalidCollectionConfig
+        );
+        // A loan at max LTV has a health ratio of 10000 * 10000 / max_ltv bps and must
+        // not be liquidatable the moment it is originated
+        require!(
+            self.liquidation_threshold as u64 * self.max_ltv as u64 <= 10000 * 10000,
+            LoanError::InvalidCollectionConfig
+        );
+        
+        config.max_ltv = self.max_ltv;
+        config.liquidation_threshold = self.liquidation_threshold;
+        config.max_duration = self.max_duration;
+        config.enabled = self.enabled;
+        config.oracle_source = self.oracle_source;
+        config.price_feed = self.price_feed;
+        config.pyth_feed_id = self.pyth_feed_id;
+        config.max_price_age = self.max_price_age;
+        config.max_confidence_bps = self.max_confidence_bps;
+        config.late_fee_policy = self.late_fee_policy;
+        
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateCollectionConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        has_one = authority
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    /// CHECK: Verified collection mint the config applies to
+    pub collection_mint: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CollectionConfig::LEN,
+        seeds = [
+            b"collection",
+            collection_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_handler(ctx: Context<CreateCollectionConfig>, params: CollectionConfigParams) -> Result<()> {
+    let config = &mut ctx.accounts.collection_config;
+    
+    config.collection_mint = ctx.accounts.collection_mint.key();
+    config.bump = ctx.bumps.collection_config;
+    params.apply(config)?;
+    
+    msg!("Collection config created for: {}", config.collection_mint);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateCollectionConfig<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        has_one = authority
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"collection",
+            collection_config.collection_mint.as_ref()
+        ],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+}
+
+pub fn update_handler(ctx: Context<UpdateCollectionConfig>, params: CollectionConfigParams) -> Result<()> {
+    let config = &mut ctx.accounts.collection_config;
+    
+    params.apply(config)?;
+    
+    msg!("Collection config updated for: {}", config.collection_mint);
+    
+    Ok(())
+}
@@ -1,8 +1,12 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{TokenAccount, Mint};
+use anchor_spl::token::{Token, TokenAccount, Mint, Transfer};
 
 use crate::states::*;
+use crate::errors::*;
 use crate::events::*;
+use crate::utils::{calculate_fee, calculate_health_ratio, calculate_installment, checked_add, checked_sub};
+use crate::oracle::collateral_value;
+use crate::pnft::{validate_collateral, validate_collection};
 
 #[derive(Accounts)]
 #[instruction(loan_amount: u64, duration: i64, interest_rate: u16)]
@@ -13,15 +17,68 @@ pub struct CreateLoan<'info> {
     #[account(mut)]
     pub lender: Signer<'info>,
     
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    #[account(
+        seeds = [
+            b"collection",
+            collection_config.collection_mint.as_ref()
+        ],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    /// CHECK: Floor price feed of the collection, validated in oracle::load_price
+    pub price_feed: UncheckedAccount<'info>,
+    
     // pNFT accounts
     pub collateral_mint: Account<'info, Mint>,
     
+    /// CHECK: Metadata PDA of the collateral mint, deserialized in the handler
+    #[account(
+        seeds = [b"metadata", mpl_token_metadata::ID.as_ref(), collateral_mint.key().as_ref()],
+        seeds::program = mpl_token_metadata::ID,
+        bump
+    )]
+    pub collateral_metadata: UncheckedAccount<'info>,
+    
+    /// CHECK: Master edition PDA of the collateral mint, deserialized in the handler
+    #[account(
+        seeds = [b"metadata", mpl_token_metadata::ID.as_ref(), collateral_mint.key().as_ref(), b"edition"],
+        seeds::program = mpl_token_metadata::ID,
+        bump
+    )]
+    pub collateral_edition: UncheckedAccount<'info>,
+    
+    // Mint the principal is denominated in
+    pub loan_mint: Account<'info, Mint>,
+    
+    // Principal token accounts
+    #[account(
+        mut,
+        constraint = lender_usdc.mint == loan_mint.key(),
+        constraint = lender_usdc.owner == lender.key()
+    )]
+    pub lender_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = borrower_usdc.mint == loan_mint.key(),
+        constraint = borrower_usdc.owner == borrower.key()
+    )]
+    pub borrower_usdc: Account<'info, TokenAccount>,
+    
     #[account(
         mut,
-        constraint = collateral_token.mint == collateral_mint.key(),
-        constraint = collateral_token.owner == borrower.key()
+        constraint = treasury_usdc.mint == loan_mint.key(),
+        constraint = treasury_usdc.owner == protocol.treasury
     )]
-    pub collateral_token: Account<'info, TokenAccount>,
+    pub treasury_usdc: Account<'info, TokenAccount>,
     
     // Loan PDA
     #[account(
@@ -38,6 +95,7 @@ pub struct CreateLoan<'info> {
     pub loan: Account<'info, Loan>,
     
     pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
 }
 
 pub fn handler(
@@ -45,32 +103,255 @@ pub fn handler(
     loan_amount: u64,
     duration: i64,
     interest_rate: u16,
+    schedule: RepaymentSchedule,
 ) -> Result<()> {
-    let loan = &mut ctx.accounts.loan;
-    let clock = Clock::get()?;
+    let metadata = validate_collateral(
+        &ctx.accounts.protocol,
+        &ctx.accounts.collateral_mint,
+        &ctx.accounts.collateral_metadata,
+        &ctx.accounts.collateral_edition,
+    )?;
+    let collection_config = &ctx.accounts.collection_config;
+    validate_collection(&metadata, collection_config)?;
+    require!(duration <= collection_config.max_duration, LoanError::InvalidLoanDuration);
+    validate_loan_terms(
+        &ctx.accounts.protocol,
+        &ctx.accounts.loan_mint.key(),
+        loan_amount,
+        duration,
+        interest_rate,
+    )?;
+    check_max_ltv(
+        collection_config,
+        &ctx.accounts.price_feed,
+        ctx.accounts.loan_mint.decimals,
+        loan_amount,
+    )?;
+    
+    PrincipalDisbursement {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        source: ctx.accounts.lender_usdc.to_account_info(),
+        authority: ctx.accounts.lender.to_account_info(),
+        borrower_usdc: ctx.accounts.borrower_usdc.to_account_info(),
+        treasury_usdc: ctx.accounts.treasury_usdc.to_account_info(),
+    }
+    .invoke(loan_amount, ctx.accounts.protocol.fee_rate)?;
 
-    // Initialize loan account
-    loan.borrower = ctx.accounts.borrower.key();
-    loan.lender = ctx.accounts.lender.key();
-    loan.collateral_mint = ctx.accounts.collateral_mint.key();
-    loan.loan_amount = loan_amount;
-    loan.outstanding_amount = loan_amount;
-    loan.interest_rate = interest_rate;
-    loan.duration = duration;
-    loan.start_time = clock.unix_timestamp;
-    loan.status = LoanStatus::Active;
-    loan.liquidation_threshold = 8000; // 80% LTV
-    loan.bump = ctx.bumps.loan;
+    init_loan(
+        &mut ctx.accounts.loan,
+        ctx.bumps.loan,
+        Origination {
+            borrower: ctx.accounts.borrower.key(),
+            lender: ctx.accounts.lender.key(),
+            collateral_mint: ctx.accounts.collateral_mint.key(),
+            loan_mint: ctx.accounts.loan_mint.key(),
+            terms: LoanTerms { loan_amount, interest_rate, duration, schedule },
+            status: LoanStatus::Pending, // activated by deposit_collateral or lock_collateral
+            pool_funded: false,
+        },
+        &ctx.accounts.collection_config,
+        &mut ctx.accounts.protocol,
+    )?;
+
+    Ok(())
+}
 
+// Check principal, duration and APR against the protocol bounds
+pub fn validate_loan_terms(
+    protocol: &Protocol,
+    loan_mint: &Pubkey,
+    loan_amount: u64,
+    duration: i64,
+    interest_rate: u16,
+) -> Result<()> {
+    require!(
+        loan_amount >= protocol.min_principal(loan_mint),
+        LoanError::InsufficientLoanAmount
+    );
+    require!(
+        duration >= protocol.min_duration && duration <= protocol.max_duration,
+        LoanError::InvalidLoanDuration
+    );
+    require!(
+        interest_rate >= protocol.min_interest_rate && interest_rate <= protocol.max_interest_rate,
+        LoanError::InvalidInterestRate
+    );
+    
+    Ok(())
+}
+
+// Derive the installment plan from the loan terms; start_time, duration and the
+// outstanding balance must be set
+pub fn setup_schedule(loan: &mut Loan, schedule: RepaymentSchedule) -> Result<()> {
+    let count = schedule.installment_count(loan.duration)?;
+    loan.schedule = schedule;
+    loan.installments_paid = 0;
+    loan.late_payments = 0;
+    
+    match schedule {
+        RepaymentSchedule::Bullet => {
+            loan.installment_amount = 0;
+            loan.installment_count = count;
+            loan.next_due_time = loan.start_time + loan.duration;
+        }
+        RepaymentSchedule::Amortizing { period } => {
+            loan.installment_amount = calculate_installment(
+                loan.outstanding_principal(),
+                loan.interest_rate,
+                period,
+                count,
+            )
+            .ok_or(LoanError::InvalidRepaymentSchedule)?;
+            loan.installment_count = count;
+            loan.next_due_time = loan.start_time + period;
+        }
+    }
+    
+    Ok(())
+}
+
+// Parties, mints and terms of a new loan; the rest comes from the collection and protocol
+pub struct Origination {
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub loan_mint: Pubkey,
+    pub terms: LoanTerms,
+    pub status: LoanStatus,
+    pub pool_funded: bool,
+}
+
+// Initialize a loan starting now, count it in the protocol totals and emit LoanCreated
+pub fn init_loan(
+    loan: &mut Account<Loan>,
+    bump: u8,
+    origination: Origination,
+    collection_config: &CollectionConfig,
+    protocol: &mut Protocol,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let terms = origination.terms;
+    
+    loan.borrower = origination.borrower;
+    loan.lender = origination.lender;
+    loan.collateral_mint = origination.collateral_mint;
+    loan.collection = collection_config.collection_mint;
+    loan.loan_mint = origination.loan_mint;
+    loan.loan_amount = terms.loan_amount;
+    loan.outstanding_amount = terms.loan_amount;
+    loan.accrued_interest = 0;
+    loan.interest_rate = terms.interest_rate;
+    loan.duration = terms.duration;
+    loan.start_time = clock.unix_timestamp;
+    loan.last_accrual_time = clock.unix_timestamp;
+    loan.status = origination.status;
+    loan.collateral_mode = CollateralMode::Escrow;
+    loan.liquidation_threshold = collection_config.liquidation_threshold;
+    setup_schedule(loan, terms.schedule)?;
+    loan.late_fee_policy = collection_config.late_fee_policy
+        .unwrap_or(protocol.late_fee_policy);
+    loan.compounding = protocol.compounding;
+    loan.pool_funded = origination.pool_funded;
+    loan.bump = bump;
+    
+    protocol.total_loans = protocol.total_loans
+        .checked_add(1)
+        .ok_or(LoanError::MathOverflow)?;
+    protocol.total_volume = checked_add(protocol.total_volume, terms.loan_amount)?;
+    
     emit!(LoanCreated {
         loan: loan.key(),
         borrower: loan.borrower,
         lender: loan.lender,
         collateral_mint: loan.collateral_mint,
-        amount: loan_amount,
-        duration,
-        interest_rate,
+        amount: terms.loan_amount,
+        duration: terms.duration,
+        interest_rate: terms.interest_rate,
     });
+    
+    Ok(())
+}
 
+// Initialize the vault of a loan whose collateral was escrowed in the same instruction
+pub fn init_vault(vault: &mut Account<Vault>, bump: u8, loan: &Account<Loan>) -> Result<()> {
+    vault.loan = loan.key();
+    vault.collateral_mint = loan.collateral_mint;
+    vault.bump = bump;
+    
+    emit!(CollateralDeposited {
+        loan: loan.key(),
+        collateral_mint: loan.collateral_mint,
+        amount: 1,
+    });
+    
     Ok(())
 }
+
+// Value the collateral through the oracle and reject loans above the collection's max LTV
+pub fn check_max_ltv(
+    collection_config: &CollectionConfig,
+    price_feed: &AccountInfo,
+    loan_mint_decimals: u8,
+    loan_amount: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let collateral_value = collateral_value(
+        collection_config,
+        price_feed,
+        loan_mint_decimals,
+        clock.unix_timestamp,
+    )?;
+    require!(collateral_value > 0, LoanError::InsufficientCollateral);
+    
+    // LTV in bps: loan_amount / collateral_value
+    let ltv = calculate_health_ratio(loan_amount, collateral_value)?;
+    require!(
+        ltv <= collection_config.max_ltv as u64,
+        LoanError::InsufficientCollateral
+    );
+    
+    Ok(())
+}
+
+// Disburse principal: origination fee to treasury, remainder to borrower
+pub struct PrincipalDisbursement<'info> {
+    pub token_program: AccountInfo<'info>,
+    pub source: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub borrower_usdc: AccountInfo<'info>,
+    pub treasury_usdc: AccountInfo<'info>,
+}
+
+impl<'info> PrincipalDisbursement<'info> {
+    pub fn invoke(&self, loan_amount: u64, fee_rate: u16) -> Result<()> {
+        self.invoke_signed(loan_amount, fee_rate, &[])
+    }
+    
+    pub fn invoke_signed(&self, loan_amount: u64, fee_rate: u16, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let fee = calculate_fee(loan_amount, fee_rate)?;
+        
+        let fee_ctx = CpiContext::new_with_signer(
+            self.token_program.clone(),
+            Transfer {
+                from: self.source.clone(),
+                to: self.treasury_usdc.clone(),
+                authority: self.authority.clone(),
+            },
+            signer_seeds,
+        );
+        
+        anchor_spl::token::transfer(fee_ctx, fee)?;
+        
+        let principal_ctx = CpiContext::new_with_signer(
+            self.token_program.clone(),
+            Transfer {
+                from: self.source.clone(),
+                to: self.borrower_usdc.clone(),
+                authority: self.authority.clone(),
+            },
+            signer_seeds,
+        );
+        
+        anchor_spl::token::transfer(principal_ctx, checked_sub(loan_amount, fee)?)
+    }
+}
@@ -1,8 +1,11 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount, Transfer};
+use anchor_spl::token::{Token, TokenAccount, Mint};
+use anchor_spl::associated_token::AssociatedToken;
 
 use crate::states::*;
+use crate::errors::*;
 use crate::events::*;
+use crate::pnft::*;
 
 #[derive(Accounts)]
 pub struct DepositCollateral<'info> {
@@ -12,7 +15,7 @@ pub struct DepositCollateral<'info> {
     #[account(
         mut,
         constraint = loan.borrower == borrower.key(),
-        constraint = loan.status == LoanStatus::Active
+        constraint = loan.status == LoanStatus::Pending
     )]
     pub loan: Account<'info, Loan>,
     
@@ -22,6 +25,15 @@ pub struct DepositCollateral<'info> {
     )]
     pub vault: Account<'info, Vault>,
     
+    // pNFT accounts
+    #[account(address = loan.collateral_mint)]
+    pub collateral_mint: Account<'info, Mint>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
     #[account(
         mut,
         constraint = borrower_token.mint == loan.collateral_mint,
@@ -36,24 +48,40 @@ pub struct DepositCollateral<'info> {
     )]
     pub vault_token: Account<'info, TokenAccount>,
     
+    /// CHECK: Token record of borrower_token, validated by Token Metadata
+    #[account(mut)]
+    pub borrower_token_record: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token record of vault_token, validated by Token Metadata
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 pub fn handler(ctx: Context<DepositCollateral>) -> Result<()> {
-    let loan = &mut ctx.accounts.loan;
-    let vault = &mut ctx.accounts.vault;
+    // Transfer pNFT from borrower to vault
+    ctx.accounts.pnft.transfer(
+        &ctx.accounts.collateral_mint,
+        PnftHolder::new(ctx.accounts.borrower_token.to_account_info(), ctx.accounts.borrower.to_account_info(), &ctx.accounts.borrower_token_record),
+        PnftHolder::new(ctx.accounts.vault_token.to_account_info(), ctx.accounts.vault.to_account_info(), &ctx.accounts.vault_token_record),
+        ctx.accounts.borrower.to_account_info(),
+        ctx.accounts.borrower.to_account_info(),
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.associated_token_program,
+    )
+    .invoke()?;
     
-    // Transfer NFT from borrower to vault
-    let transfer_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Transfer {
-            from: ctx.accounts.borrower_token.to_account_info(),
-            to: ctx.accounts.vault_token.to_account_info(),
-            authority: ctx.accounts.borrower.to_account_info(),
-        },
-    );
+    // Only activate the loan once the vault actually holds the collateral
+    ctx.accounts.vault_token.reload()?;
+    require!(ctx.accounts.vault_token.amount == 1, LoanError::CollateralNotInVault);
     
-    anchor_spl::token::transfer(transfer_ctx, 1)?; // NFTs have amount 1
+    let loan = &mut ctx.accounts.loan;
+    loan.status = LoanStatus::Active;
+    loan.collateral_mode = CollateralMode::Escrow;
     
     emit!(CollateralDeposited {
         loan: loan.key(),
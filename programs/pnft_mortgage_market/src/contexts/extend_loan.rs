@@ -0,0 +1,130 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount, Mint, Transfer};
+
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::utils::checked_add;
+use crate::contexts::create_loan::{check_max_ltv, setup_schedule, validate_loan_terms};
+
+#[derive(Accounts)]
+pub struct ExtendLoan<'info> {
+    pub borrower: Signer<'info>,
+    
+    pub lender: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    #[account(
+        seeds = [
+            b"collection",
+            loan.collection.as_ref()
+        ],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    /// CHECK: Floor price feed of the collection, validated in oracle::load_price
+    pub price_feed: UncheckedAccount<'info>,
+    
+    // A pool has no signer to agree to new terms, so only direct loans can be extended
+    #[account(
+        mut,
+        constraint = loan.borrower == borrower.key(),
+        constraint = !loan.pool_funded @ LoanError::PoolLoanNotExtendable,
+        constraint = loan.lender == lender.key(),
+        constraint = loan.status == LoanStatus::Active @ LoanError::LoanNotActive
+    )]
+    pub loan: Account<'info, Loan>,
+    
+    #[account(address = loan.loan_mint)]
+    pub loan_mint: Account<'info, Mint>,
+    
+    // USDC token accounts for paying accrued interest
+    #[account(
+        mut,
+        constraint = borrower_usdc.mint == loan.loan_mint,
+        constraint = borrower_usdc.owner == borrower.key()
+    )]
+    pub borrower_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = lender_usdc.mint == loan.loan_mint,
+        constraint = lender_usdc.owner == lender.key()
+    )]
+    pub lender_usdc: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(
+    ctx: Context<ExtendLoan>,
+    duration: i64,
+    interest_rate: u16,
+    capitalize_interest: bool,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let loan = &mut ctx.accounts.loan;
+    
+    loan.accrue_interest(clock.unix_timestamp)?;
+    let accrued_interest = loan.accrued_interest;
+    
+    if capitalize_interest {
+        // Accrued interest becomes principal and bears interest under the new terms
+        loan.accrued_interest = 0;
+    } else if accrued_interest > 0 {
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.borrower_usdc.to_account_info(),
+                to: ctx.accounts.lender_usdc.to_account_info(),
+                authority: ctx.accounts.borrower.to_account_info(),
+            },
+        );
+        
+        anchor_spl::token::transfer(transfer_ctx, accrued_interest)?;
+        loan.apply_payment(accrued_interest);
+    }
+    
+    // The new term runs from now on the remaining principal
+    let principal = loan.outstanding_principal();
+    require!(
+        duration <= ctx.accounts.collection_config.max_duration,
+        LoanError::InvalidLoanDuration
+    );
+    validate_loan_terms(&ctx.accounts.protocol, &loan.loan_mint, principal, duration, interest_rate)?;
+    check_max_ltv(
+        &ctx.accounts.collection_config,
+        &ctx.accounts.price_feed,
+        ctx.accounts.loan_mint.decimals,
+        principal,
+    )?;
+    
+    loan.interest_rate = interest_rate;
+    loan.duration = duration;
+    loan.start_time = clock.unix_timestamp;
+    loan.last_accrual_time = clock.unix_timestamp;
+    let schedule = loan.schedule;
+    setup_schedule(loan, schedule)?;
+    
+    let capitalized_interest = if capitalize_interest { accrued_interest } else { 0 };
+    ctx.accounts.protocol.total_volume = checked_add(ctx.accounts.protocol.total_volume, capitalized_interest)?;
+    
+    emit!(LoanExtended {
+        loan: loan.key(),
+        borrower: loan.borrower,
+        lender: loan.lender,
+        duration,
+        interest_rate,
+        capitalized_interest,
+        outstanding_amount: loan.outstanding_amount,
+    });
+    
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use anchor_lang::prelude::*;
+
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FloorPriceFeedParams {
+    pub exponent: i32,
+    pub max_deviation_bps: u16,
+    pub twap_window: i64,           // seconds
+    pub confirmations_required: u8, // updaters agreeing on an outlier to accept a market move
+}
+
+#[derive(Accounts)]
+pub struct CreateFloorPriceFeed<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        has_one = authority
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    #[account(
+        seeds = [
+            b"collection",
+            collection_config.collection_mint.as_ref()
+        ],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + FloorPriceFeed::LEN,
+        seeds = [
+            b"floor_price",
+            collection_config.collection_mint.as_ref()
+        ],
+        bump
+    )]
+    pub floor_price_feed: Account<'info, FloorPriceFeed>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_handler(ctx: Context<CreateFloorPriceFeed>, params: FloorPriceFeedParams) -> Result<()> {
+    require!(
+        params.max_deviation_bps > 0
+            && params.twap_window > 0
+            && params.confirmations_required > 1
+            && params.confirmations_required as usize <= FloorPriceFeed::MAX_CONFIRMATIONS,
+        LoanError::InvalidCollectionConfig
+    );
+    
+    let feed = &mut ctx.accounts.floor_price_feed;
+    
+    feed.collection_mint = ctx.accounts.collection_config.collection_mint;
+    feed.exponent = params.exponent;
+    feed.max_deviation_bps = params.max_deviation_bps;
+    feed.twap_window = params.twap_window;
+    feed.head = 0;
+    feed.count = 0;
+    feed.confirmations_required = params.confirmations_required;
+    feed.candidate_confirmations = 0;
+    feed.bump = ctx.bumps.floor_price_feed;
+    
+    msg!("Floor price feed created for: {}", feed.collection_mint);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateFloorPrice<'info> {
+    pub updater: Signer<'info>,
+    
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.is_price_updater(&updater.key()) @ LoanError::UnauthorizedPriceUpdater
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    #[account(
+        seeds = [
+            b"collection",
+            floor_price_feed.collection_mint.as_ref()
+        ],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"floor_price",
+            floor_price_feed.collection_mint.as_ref()
+        ],
+        bump = floor_price_feed.bump
+    )]
+    pub floor_price_feed: Account<'info, FloorPriceFeed>,
+}
+
+pub fn update_handler(
+    ctx: Context<UpdateFloorPrice>,
+    price: u64,
+    confidence: u64,
+    timestamp: i64,
+) -> Result<()> {
+    let feed = &mut ctx.accounts.floor_price_feed;
+    let config = &ctx.accounts.collection_config;
+    let clock = Clock::get()?;
+    
+    // Reject stale, out-of-order and future observations
+    require!(price > 0, LoanError::InvalidOraclePrice);
+    require!(
+        timestamp <= clock.unix_timestamp
+            && clock.unix_timestamp - timestamp <= config.max_price_age as i64,
+        LoanError::InvalidOraclePrice
+    );
+    if let Some(last_timestamp) = feed.last_timestamp() {
+        require!(timestamp > last_timestamp, LoanError::InvalidOraclePrice);
+    }
+    
+    // Hold outliers against the current TWAP until consistent updates confirm them
+    let observation = PriceObservation {
+        price,
+        confidence,
+        timestamp,
+    };
+    if !feed.record(observation, ctx.accounts.updater.key(), clock.unix_timestamp) {
+        emit!(FloorPriceOutlier {
+            feed: feed.key(),
+            collection_mint: feed.collection_mint,
+            price,
+            confirmations: feed.candidate_confirmations,
+            timestamp,
+        });
+        return Ok(());
+    }
+    
+    emit!(FloorPriceUpdated {
+        feed: feed.key(),
+        collection_mint: feed.collection_mint,
+        price,
+        twap: feed.twap(clock.unix_timestamp).unwrap_or(price),
+        timestamp,
+    });
+    
+    Ok(())
+}
@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
-use crate::states::Protocol;
+use mpl_token_metadata::types::TokenStandard;
+
+use crate::states::{AuctionConfig, AuctionMode, LateFeePolicy, Protocol};
+use crate::interest::Compounding;
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
@@ -30,6 +33,28 @@ pub fn handler(ctx: Context<Initialize>) -> Result<()> {
     protocol.fee_rate = 50; // 0.5% fee
     protocol.total_loans = 0;
     protocol.total_volume = 0;
+    protocol.allowed_token_standards = Protocol::token_standard_flag(TokenStandard::ProgrammableNonFungible);
+    protocol.min_interest_rate = 100; // 1% APR
+    protocol.max_interest_rate = 10000; // 100% APR
+    protocol.min_duration = 24 * 60 * 60; // 1 day
+    protocol.max_duration = 365 * 24 * 60 * 60; // 1 year
+    protocol.late_fee_policy = LateFeePolicy {
+        grace_period: 3 * 24 * 60 * 60, // 3 days
+        flat_fee_bps: 100,              // 1%
+        daily_fee_bps: 10,              // 0.1% per day
+        treasury_share_bps: 2000,       // 20% of late fees
+    };
+    protocol.compounding = Compounding::Simple;
+    protocol.auction_config = AuctionConfig {
+        duration: 24 * 60 * 60,         // 24 hours
+        starting_price_bps: 5000,       // 50% of the debt
+        min_bid_increment_bps: 100,     // 1%
+        reserve_price_bps: 0,           // any bid sells
+        extension_window: 10 * 60,      // bids in the last 10 minutes
+        extension_duration: 10 * 60,    // extend by 10 minutes
+        max_extension: 2 * 60 * 60,     // up to 2 hours in total
+        mode: AuctionMode::English,
+    };
     protocol.bump = ctx.bumps.protocol;
     
     msg!("Protocol initialized with authority: {}", protocol.authority);
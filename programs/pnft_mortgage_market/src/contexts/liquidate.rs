@@ -1,15 +1,25 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::token::{Token, TokenAccount, Mint};
+use anchor_spl::associated_token::AssociatedToken;
 
 use crate::states::*;
 use crate::errors::*;
 use crate::events::*;
+use crate::utils::checked_mul_div;
+use crate::pnft::*;
+use crate::oracle::collateral_value;
 
 #[derive(Accounts)]
 pub struct LiquidateLoan<'info> {
     #[account(mut)]
     pub liquidator: Signer<'info>,
     
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Box<Account<'info, Protocol>>,
+    
     #[account(
         mut,
         constraint = loan.status == LoanStatus::Active
@@ -22,6 +32,21 @@ pub struct LiquidateLoan<'info> {
     )]
     pub vault: Account<'info, Vault>,
     
+    #[account(
+        seeds = [
+            b"collection",
+            loan.collection.as_ref()
+        ],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    /// CHECK: Floor price feed of the collection, validated in oracle::load_price
+    pub price_feed: UncheckedAccount<'info>,
+    
+    #[account(address = loan.loan_mint)]
+    pub loan_mint: Account<'info, Mint>,
+    
     // Auction PDA
     #[account(
         init,
@@ -35,6 +60,21 @@ pub struct LiquidateLoan<'info> {
     )]
     pub auction: Account<'info, Auction>,
     
+    // Bid escrow: a program-derived token account owned by the auction PDA,
+    // so it cannot be created ahead of the liquidation
+    #[account(
+        init,
+        payer = liquidator,
+        seeds = [
+            b"auction_escrow",
+            loan.key().as_ref()
+        ],
+        bump,
+        token::mint = loan_mint,
+        token::authority = auction
+    )]
+    pub auction_usdc: Box<Account<'info, TokenAccount>>,
+    
     #[account(
         mut,
         constraint = vault_token.mint == loan.collateral_mint,
@@ -42,33 +82,123 @@ pub struct LiquidateLoan<'info> {
     )]
     pub vault_token: Account<'info, TokenAccount>,
     
+    // Delegate-locked collateral is pulled from the borrower into the vault
+    /// CHECK: Loan borrower, owner of borrower_token
+    #[account(address = loan.borrower)]
+    pub borrower: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = borrower_token.mint == loan.collateral_mint,
+        constraint = borrower_token.owner == loan.borrower
+    )]
+    pub borrower_token: Option<Account<'info, TokenAccount>>,
+    
+    // pNFT accounts
+    #[account(address = loan.collateral_mint)]
+    pub collateral_mint: Account<'info, Mint>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
+    /// CHECK: Token record of borrower_token, validated by Token Metadata
+    #[account(mut)]
+    pub borrower_token_record: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token record of vault_token, validated by Token Metadata
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 pub fn handler(ctx: Context<LiquidateLoan>) -> Result<()> {
-    let loan = &mut ctx.accounts.loan;
-    let auction = &mut ctx.accounts.auction;
     let clock = Clock::get()?;
     
-    // Check if loan is liquidatable
-    // For demo purposes, we'll use a mock collateral value
-    // In production, this would come from oracle
-    let mock_collateral_value = 1000 * 1_000_000; // 1000 USDC
+    // Accrue first so the health check sees the debt as of now
+    ctx.accounts.loan.accrue_interest(clock.unix_timestamp)?;
     
+    // Expired and delinquent loans need no price; otherwise check the collection floor
+    let accounts = &ctx.accounts;
     require!(
-        loan.is_liquidatable(clock.unix_timestamp, mock_collateral_value),
+        accounts.loan.is_liquidatable(clock.unix_timestamp, || {
+            collateral_value(
+                &accounts.collection_config,
+                &accounts.price_feed,
+                accounts.loan_mint.decimals,
+                clock.unix_timestamp,
+            )
+        })?,
         LoanError::NotLiquidatable
     );
     
-    // Initialize auction
+    // Move delegate-locked collateral into the vault under delegate authority
+    if ctx.accounts.loan.collateral_mode == CollateralMode::DelegateLock {
+        let borrower_token = ctx.accounts.borrower_token.as_ref()
+            .ok_or(LoanError::MissingCollateralAccount)?;
+        let loan_key = ctx.accounts.loan.key();
+        let vault_seeds: &[&[&[u8]]] = &[&[b"vault", loan_key.as_ref(), &[ctx.accounts.vault.bump]]];
+        
+        ctx.accounts.pnft.lock(
+            &ctx.accounts.collateral_mint,
+            PnftHolder::new(borrower_token.to_account_info(), ctx.accounts.borrower.to_account_info(), &ctx.accounts.borrower_token_record),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.liquidator.to_account_info(),
+            &ctx.accounts.system_program,
+            &ctx.accounts.token_program,
+        )
+        .unlock(vault_seeds)?;
+        
+        ctx.accounts.pnft.transfer(
+            &ctx.accounts.collateral_mint,
+            PnftHolder::new(borrower_token.to_account_info(), ctx.accounts.borrower.to_account_info(), &ctx.accounts.borrower_token_record),
+            PnftHolder::new(ctx.accounts.vault_token.to_account_info(), ctx.accounts.vault.to_account_info(), &ctx.accounts.vault_token_record),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.liquidator.to_account_info(),
+            &ctx.accounts.system_program,
+            &ctx.accounts.token_program,
+            &ctx.accounts.associated_token_program,
+        )
+        .invoke_signed(vault_seeds)?;
+        
+        ctx.accounts.vault_token.reload()?;
+        require!(ctx.accounts.vault_token.amount == 1, LoanError::CollateralNotInVault);
+        ctx.accounts.loan.collateral_mode = CollateralMode::Escrow;
+    }
+    
+    let config = &ctx.accounts.protocol.auction_config;
+    let loan = &mut ctx.accounts.loan;
+    let auction = &mut ctx.accounts.auction;
+    
+    // Initialize auction, pricing off the debt accrued to now
     auction.loan = loan.key();
     auction.collateral_mint = loan.collateral_mint;
-    auction.starting_price = loan.outstanding_amount / 2; // Start at 50% of loan value
+    auction.starting_price = checked_mul_div(loan.outstanding_amount, config.starting_price_bps as u64, 10000)?;
     auction.current_bid = 0;
     auction.current_bidder = Pubkey::default();
-    auction.end_time = clock.unix_timestamp + 24 * 3600; // 24 hours
+    auction.end_time = clock.unix_timestamp
+        .checked_add(config.duration)
+        .ok_or(LoanError::MathOverflow)?;
     auction.status = AuctionStatus::Active;
+    auction.min_bid_increment_bps = config.min_bid_increment_bps;
+    auction.reserve_price = checked_mul_div(loan.outstanding_amount, config.reserve_price_bps as u64, 10000)?;
+    auction.extension_window = config.extension_window;
+    auction.extension_duration = config.extension_duration;
+    auction.max_end_time = auction.end_time
+        .checked_add(config.max_extension)
+        .ok_or(LoanError::MathOverflow)?;
+    auction.mode = config.mode;
+    auction.start_time = clock.unix_timestamp;
+    auction.floor_price = match config.mode {
+        AuctionMode::English => 0,
+        AuctionMode::Dutch { floor_price_bps, .. } => {
+            checked_mul_div(loan.outstanding_amount, floor_price_bps as u64, 10000)?
+        }
+    };
     auction.bump = ctx.bumps.auction;
     
     // Update loan status
@@ -84,6 +214,7 @@ pub fn handler(ctx: Context<LiquidateLoan>) -> Result<()> {
         loan: loan.key(),
         collateral_mint: loan.collateral_mint,
         starting_price: auction.starting_price,
+        reserve_price: auction.reserve_price,
         end_time: auction.end_time,
     });
     
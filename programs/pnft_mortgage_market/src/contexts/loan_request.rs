@@ -0,0 +1,486 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Mint};
+use anchor_spl::associated_token::AssociatedToken;
+
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::contexts::create_loan::{check_max_ltv, init_loan, init_vault, validate_loan_terms, Origination, PrincipalDisbursement};
+use crate::pnft::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LoanRequestParams {
+    pub loan_amount: u64,
+    pub interest_rate: u16,         // basis points
+    pub duration: i64,              // seconds
+    pub schedule: RepaymentSchedule,
+    pub expiry: i64,                // unix timestamp
+}
+
+#[derive(Accounts)]
+pub struct CreateLoanRequest<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Box<Account<'info, Protocol>>,
+    
+    #[account(
+        seeds = [
+            b"collection",
+            collection_config.collection_mint.as_ref()
+        ],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Box<Account<'info, CollectionConfig>>,
+    
+    // pNFT accounts
+    pub collateral_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
+    #[account(
+        mut,
+        constraint = borrower_token.mint == collateral_mint.key(),
+        constraint = borrower_token.owner == borrower.key()
+    )]
+    pub borrower_token: Box<Account<'info, TokenAccount>>,
+    
+    // Mint the principal is denominated in
+    pub loan_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + LoanRequest::LEN,
+        seeds = [
+            b"request",
+            borrower.key().as_ref(),
+            collateral_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub request: Box<Account<'info, LoanRequest>>,
+    
+    // Collateral escrow owned by the request PDA
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = request
+    )]
+    pub request_token: Box<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Token record of borrower_token, validated by Token Metadata
+    #[account(mut)]
+    pub borrower_token_record: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token record of request_token, validated by Token Metadata
+    #[account(mut)]
+    pub request_token_record: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn create_handler(ctx: Context<CreateLoanRequest>, params: LoanRequestParams) -> Result<()> {
+    let clock = Clock::get()?;
+    require!(params.expiry > clock.unix_timestamp, LoanError::RequestExpired);
+    
+    let metadata = validate_collateral(
+        &ctx.accounts.protocol,
+        &ctx.accounts.collateral_mint,
+        &ctx.accounts.pnft.collateral_metadata,
+        &ctx.accounts.pnft.collateral_edition,
+    )?;
+    let collection_config = &ctx.accounts.collection_config;
+    validate_collection(&metadata, collection_config)?;
+    require!(params.duration <= collection_config.max_duration, LoanError::InvalidLoanDuration);
+    validate_loan_terms(
+        &ctx.accounts.protocol,
+        &ctx.accounts.loan_mint.key(),
+        params.loan_amount,
+        params.duration,
+        params.interest_rate,
+    )?;
+    params.schedule.installment_count(params.duration)?;
+    
+    // Escrow pNFT with the request until it is filled or cancelled
+    ctx.accounts.pnft.transfer(
+        &ctx.accounts.collateral_mint,
+        PnftHolder::new(ctx.accounts.borrower_token.to_account_info(), ctx.accounts.borrower.to_account_info(), &ctx.accounts.borrower_token_record),
+        PnftHolder::new(ctx.accounts.request_token.to_account_info(), ctx.accounts.request.to_account_info(), &ctx.accounts.request_token_record),
+        ctx.accounts.borrower.to_account_info(),
+        ctx.accounts.borrower.to_account_info(),
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.associated_token_program,
+    )
+    .invoke()?;
+    
+    ctx.accounts.request_token.reload()?;
+    require!(ctx.accounts.request_token.amount == 1, LoanError::CollateralNotInVault);
+    
+    let request = &mut ctx.accounts.request;
+    request.borrower = ctx.accounts.borrower.key();
+    request.collateral_mint = ctx.accounts.collateral_mint.key();
+    request.collection = ctx.accounts.collection_config.collection_mint;
+    request.loan_mint = ctx.accounts.loan_mint.key();
+    request.loan_amount = params.loan_amount;
+    request.interest_rate = params.interest_rate;
+    request.duration = params.duration;
+    request.schedule = params.schedule;
+    request.expiry = params.expiry;
+    request.bump = ctx.bumps.request;
+    
+    emit!(LoanRequested {
+        request: request.key(),
+        borrower: request.borrower,
+        collateral_mint: request.collateral_mint,
+        loan_mint: request.loan_mint,
+        amount: request.loan_amount,
+        duration: request.duration,
+        interest_rate: request.interest_rate,
+        expiry: request.expiry,
+    });
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct FillLoanRequest<'info> {
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    
+    /// CHECK: Borrower of the request, receives the principal and the request rent
+    #[account(mut, address = request.borrower)]
+    pub borrower: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Box<Account<'info, Protocol>>,
+    
+    #[account(
+        seeds = [
+            b"collection",
+            request.collection.as_ref()
+        ],
+        bump = collection_config.bump,
+        constraint = collection_config.enabled @ LoanError::CollectionNotEligible
+    )]
+    pub collection_config: Box<Account<'info, CollectionConfig>>,
+    
+    /// CHECK: Floor price feed of the collection, validated in oracle::load_price
+    pub price_feed: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"request",
+            request.borrower.as_ref(),
+            request.collateral_mint.as_ref()
+        ],
+        bump = request.bump,
+        close = borrower
+    )]
+    pub request: Box<Account<'info, LoanRequest>>,
+    
+    #[account(
+        mut,
+        constraint = request_token.mint == request.collateral_mint,
+        constraint = request_token.owner == request.key()
+    )]
+    pub request_token: Box<Account<'info, TokenAccount>>,
+    
+    // pNFT accounts
+    #[account(address = request.collateral_mint)]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
+    #[account(address = request.loan_mint)]
+    pub loan_mint: Box<Account<'info, Mint>>,
+    
+    // Principal token accounts
+    #[account(
+        mut,
+        constraint = lender_usdc.mint == loan_mint.key(),
+        constraint = lender_usdc.owner == lender.key()
+    )]
+    pub lender_usdc: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = borrower_usdc.mint == loan_mint.key(),
+        constraint = borrower_usdc.owner == request.borrower
+    )]
+    pub borrower_usdc: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = treasury_usdc.mint == loan_mint.key(),
+        constraint = treasury_usdc.owner == protocol.treasury
+    )]
+    pub treasury_usdc: Box<Account<'info, TokenAccount>>,
+    
+    // Loan PDA
+    #[account(
+        init,
+        payer = lender,
+        space = 8 + Loan::LEN,
+        seeds = [
+            b"loan",
+            request.borrower.as_ref(),
+            collateral_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub loan: Box<Account<'info, Loan>>,
+    
+    // Vault to hold collateral
+    #[account(
+        init,
+        payer = lender,
+        space = 8 + Vault::LEN,
+        seeds = [
+            b"vault",
+            loan.key().as_ref()
+        ],
+        bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+    
+    #[account(
+        init_if_needed,
+        payer = lender,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_token: Box<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Token record of request_token, validated by Token Metadata
+    #[account(mut)]
+    pub request_token_record: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token record of vault_token, validated by Token Metadata
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn fill_handler(ctx: Context<FillLoanRequest>, expected_terms: LoanTerms) -> Result<()> {
+    let clock = Clock::get()?;
+    let request = &ctx.accounts.request;
+    require!(clock.unix_timestamp <= request.expiry, LoanError::RequestExpired);
+    
+    // The request address only depends on the borrower and the pNFT, so it can be
+    // cancelled and re-posted on other terms while a fill is in flight
+    require!(request.terms() == expected_terms, LoanError::LoanTermsMismatch);
+    
+    // Protocol bounds and the collateral value may have moved since the request was posted
+    validate_loan_terms(
+        &ctx.accounts.protocol,
+        &request.loan_mint,
+        request.loan_amount,
+        request.duration,
+        request.interest_rate,
+    )?;
+    require!(
+        request.duration <= ctx.accounts.collection_config.max_duration,
+        LoanError::InvalidLoanDuration
+    );
+    check_max_ltv(
+        &ctx.accounts.collection_config,
+        &ctx.accounts.price_feed,
+        ctx.accounts.loan_mint.decimals,
+        request.loan_amount,
+    )?;
+    
+    // Move the escrowed pNFT from the request into the loan vault
+    let request_seeds: &[&[&[u8]]] = &[&[
+        b"request",
+        request.borrower.as_ref(),
+        request.collateral_mint.as_ref(),
+        &[request.bump],
+    ]];
+    
+    ctx.accounts.pnft.transfer(
+        &ctx.accounts.collateral_mint,
+        PnftHolder::new(ctx.accounts.request_token.to_account_info(), ctx.accounts.request.to_account_info(), &ctx.accounts.request_token_record),
+        PnftHolder::new(ctx.accounts.vault_token.to_account_info(), ctx.accounts.vault.to_account_info(), &ctx.accounts.vault_token_record),
+        ctx.accounts.request.to_account_info(),
+        ctx.accounts.lender.to_account_info(),
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.associated_token_program,
+    )
+    .invoke_signed(request_seeds)?;
+    
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.request_token.to_account_info(),
+            destination: ctx.accounts.borrower.to_account_info(),
+            authority: ctx.accounts.request.to_account_info(),
+        },
+        request_seeds,
+    );
+    
+    token::close_account(close_ctx)?;
+    
+    PrincipalDisbursement {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        source: ctx.accounts.lender_usdc.to_account_info(),
+        authority: ctx.accounts.lender.to_account_info(),
+        borrower_usdc: ctx.accounts.borrower_usdc.to_account_info(),
+        treasury_usdc: ctx.accounts.treasury_usdc.to_account_info(),
+    }
+    .invoke(request.loan_amount, ctx.accounts.protocol.fee_rate)?;
+    
+    ctx.accounts.vault_token.reload()?;
+    require!(ctx.accounts.vault_token.amount == 1, LoanError::CollateralNotInVault);
+    
+    // Loan on the request's terms
+    let request = &ctx.accounts.request;
+    init_loan(
+        &mut ctx.accounts.loan,
+        ctx.bumps.loan,
+        Origination {
+            borrower: request.borrower,
+            lender: ctx.accounts.lender.key(),
+            collateral_mint: request.collateral_mint,
+            loan_mint: request.loan_mint,
+            terms: request.terms(),
+            status: LoanStatus::Active,
+            pool_funded: false,
+        },
+        &ctx.accounts.collection_config,
+        &mut ctx.accounts.protocol,
+    )?;
+    init_vault(&mut ctx.accounts.vault, ctx.bumps.vault, &ctx.accounts.loan)?;
+    
+    let loan = &ctx.accounts.loan;
+    
+    emit!(LoanRequestFilled {
+        request: request.key(),
+        loan: loan.key(),
+        borrower: loan.borrower,
+        lender: loan.lender,
+    });
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelLoanRequest<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"request",
+            borrower.key().as_ref(),
+            request.collateral_mint.as_ref()
+        ],
+        bump = request.bump,
+        has_one = borrower,
+        close = borrower
+    )]
+    pub request: Box<Account<'info, LoanRequest>>,
+    
+    #[account(
+        mut,
+        constraint = request_token.mint == request.collateral_mint,
+        constraint = request_token.owner == request.key()
+    )]
+    pub request_token: Box<Account<'info, TokenAccount>>,
+    
+    // pNFT accounts
+    #[account(address = request.collateral_mint)]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = borrower
+    )]
+    pub borrower_token: Box<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Token record of request_token, validated by Token Metadata
+    #[account(mut)]
+    pub request_token_record: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token record of borrower_token, validated by Token Metadata
+    #[account(mut)]
+    pub borrower_token_record: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn cancel_handler(ctx: Context<CancelLoanRequest>) -> Result<()> {
+    let request = &ctx.accounts.request;
+    let request_seeds: &[&[&[u8]]] = &[&[
+        b"request",
+        request.borrower.as_ref(),
+        request.collateral_mint.as_ref(),
+        &[request.bump],
+    ]];
+    
+    // Return the escrowed pNFT to the borrower
+    ctx.accounts.pnft.transfer(
+        &ctx.accounts.collateral_mint,
+        PnftHolder::new(ctx.accounts.request_token.to_account_info(), ctx.accounts.request.to_account_info(), &ctx.accounts.request_token_record),
+        PnftHolder::new(ctx.accounts.borrower_token.to_account_info(), ctx.accounts.borrower.to_account_info(), &ctx.accounts.borrower_token_record),
+        ctx.accounts.request.to_account_info(),
+        ctx.accounts.borrower.to_account_info(),
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.associated_token_program,
+    )
+    .invoke_signed(request_seeds)?;
+    
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.request_token.to_account_info(),
+            destination: ctx.accounts.borrower.to_account_info(),
+            authority: ctx.accounts.request.to_account_info(),
+        },
+        request_seeds,
+    );
+    
+    token::close_account(close_ctx)?;
+    
+    emit!(LoanRequestCancelled {
+        request: request.key(),
+        borrower: request.borrower,
+        collateral_mint: request.collateral_mint,
+    });
+    
+    Ok(())
+}
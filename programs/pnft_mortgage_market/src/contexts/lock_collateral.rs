@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::{Token, TokenAccount, Mint};
+
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::pnft::*;
+
+#[derive(Accounts)]
+pub struct LockCollateral<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = loan.borrower == borrower.key(),
+        constraint = loan.status == LoanStatus::Pending
+    )]
+    pub loan: Account<'info, Loan>,
+    
+    // Vault PDA acts as the pNFT delegate
+    #[account(
+        constraint = vault.loan == loan.key()
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    // pNFT accounts
+    #[account(address = loan.collateral_mint)]
+    pub collateral_mint: Account<'info, Mint>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
+    #[account(
+        mut,
+        constraint = borrower_token.mint == loan.collateral_mint,
+        constraint = borrower_token.owner == borrower.key()
+    )]
+    pub borrower_token: Account<'info, TokenAccount>,
+    
+    /// CHECK: Token record of borrower_token, validated by Token Metadata
+    #[account(mut)]
+    pub borrower_token_record: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<LockCollateral>) -> Result<()> {
+    // Delegate the pNFT to the vault and lock it in the borrower's wallet
+    let loan_key = ctx.accounts.loan.key();
+    let vault_seeds: &[&[&[u8]]] = &[&[b"vault", loan_key.as_ref(), &[ctx.accounts.vault.bump]]];
+    
+    ctx.accounts.pnft.lock(
+        &ctx.accounts.collateral_mint,
+        PnftHolder::new(ctx.accounts.borrower_token.to_account_info(), ctx.accounts.borrower.to_account_info(), &ctx.accounts.borrower_token_record),
+        ctx.accounts.vault.to_account_info(),
+        ctx.accounts.borrower.to_account_info(),
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+    )
+    .delegate_and_lock(vault_seeds)?;
+    
+    // Only activate the loan once the vault is the delegate of the frozen token
+    ctx.accounts.borrower_token.reload()?;
+    let borrower_token = &ctx.accounts.borrower_token;
+    require!(
+        borrower_token.amount == 1
+            && borrower_token.is_frozen()
+            && borrower_token.delegate == COption::Some(ctx.accounts.vault.key()),
+        LoanError::CollateralNotLocked
+    );
+    
+    let loan = &mut ctx.accounts.loan;
+    loan.status = LoanStatus::Active;
+    loan.collateral_mode = CollateralMode::DelegateLock;
+    
+    emit!(CollateralLocked {
+        loan: loan.key(),
+        collateral_mint: loan.collateral_mint,
+        delegate: ctx.accounts.vault.key(),
+    });
+    
+    Ok(())
+}
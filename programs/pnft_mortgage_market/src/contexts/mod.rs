@@ -1,15 +1,45 @@
+// Every context module exposes its own `handler`; lib.rs always calls them
+// through the module path, so the glob re-exports only need the account types.
+#![allow(ambiguous_glob_reexports)]
+
 pub mod initialize;
+pub mod update_protocol;
+pub mod collection_config;
+pub mod floor_price;
 pub mod create_loan;
+pub mod open_loan;
 pub mod create_vault;
 pub mod deposit_collateral;
+pub mod lock_collateral;
+pub mod cancel_loan;
 pub mod repay_loan;
+pub mod repay_partial;
+pub mod pay_installment;
+pub mod extend_loan;
+pub mod refinance_loan;
+pub mod offer;
+pub mod loan_request;
+pub mod pool;
 pub mod liquidate;
 pub mod auction;
 
 pub use initialize::*;
+pub use update_protocol::*;
+pub use collection_config::*;
+pub use floor_price::*;
 pub use create_loan::*;
+pub use open_loan::*;
 pub use create_vault::*;
 pub use deposit_collateral::*;
+pub use lock_collateral::*;
+pub use cancel_loan::*;
 pub use repay_loan::*;
+pub use repay_partial::*;
+pub use pay_installment::*;
+pub use extend_loan::*;
+pub use refinance_loan::*;
+pub use offer::*;
+pub use loan_request::*;
+pub use pool::*;
 pub use liquidate::*;
 pub use auction::*;
@@ -0,0 +1,437 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Mint, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::contexts::create_loan::{check_max_ltv, init_loan, init_vault, validate_loan_terms, Origination, PrincipalDisbursement};
+use crate::pnft::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LoanOfferParams {
+    pub nonce: u64,
+    pub loan_amount: u64,           // principal per loan
+    pub interest_rate: u16,         // basis points
+    pub duration: i64,              // seconds
+    pub schedule: RepaymentSchedule,
+    pub loan_count: u16,            // number of loans the escrow funds
+}
+
+#[derive(Accounts)]
+#[instruction(params: LoanOfferParams)]
+pub struct CreateLoanOffer<'info> {
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    #[account(
+        seeds = [
+            b"collection",
+            collection_config.collection_mint.as_ref()
+        ],
+        bump = collection_config.bump,
+        constraint = collection_config.enabled @ LoanError::CollectionNotEligible
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    // Mint the principal is denominated in
+    pub loan_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = lender,
+        space = 8 + LoanOffer::LEN,
+        seeds = [
+            b"offer",
+            lender.key().as_ref(),
+            params.nonce.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub offer: Account<'info, LoanOffer>,
+    
+    // Principal escrow: a program-derived token account owned by the offer PDA,
+    // so it cannot be created ahead of the offer
+    #[account(
+        init,
+        payer = lender,
+        seeds = [
+            b"offer_escrow",
+            offer.key().as_ref()
+        ],
+        bump,
+        token::mint = loan_mint,
+        token::authority = offer
+    )]
+    pub offer_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = lender_usdc.mint == loan_mint.key(),
+        constraint = lender_usdc.owner == lender.key()
+    )]
+    pub lender_usdc: Account<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn create_handler(ctx: Context<CreateLoanOffer>, params: LoanOfferParams) -> Result<()> {
+    require!(params.loan_count > 0, LoanError::InsufficientLoanAmount);
+    require!(
+        params.duration <= ctx.accounts.collection_config.max_duration,
+        LoanError::InvalidLoanDuration
+    );
+    validate_loan_terms(
+        &ctx.accounts.protocol,
+        &ctx.accounts.loan_mint.key(),
+        params.loan_amount,
+        params.duration,
+        params.interest_rate,
+    )?;
+    params.schedule.installment_count(params.duration)?;
+    
+    // Escrow principal for every loan the offer can fund
+    let escrow_amount = params.loan_amount
+        .checked_mul(params.loan_count as u64)
+        .ok_or(LoanError::MathOverflow)?;
+    
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.lender_usdc.to_account_info(),
+            to: ctx.accounts.offer_usdc.to_account_info(),
+            authority: ctx.accounts.lender.to_account_info(),
+        },
+    );
+    
+    token::transfer(transfer_ctx, escrow_amount)?;
+    
+    let offer = &mut ctx.accounts.offer;
+    offer.lender = ctx.accounts.lender.key();
+    offer.collection = ctx.accounts.collection_config.collection_mint;
+    offer.loan_mint = ctx.accounts.loan_mint.key();
+    offer.loan_amount = params.loan_amount;
+    offer.interest_rate = params.interest_rate;
+    offer.duration = params.duration;
+    offer.schedule = params.schedule;
+    offer.loans_remaining = params.loan_count;
+    offer.nonce = params.nonce;
+    offer.bump = ctx.bumps.offer;
+    
+    emit!(OfferCreated {
+        offer: offer.key(),
+        lender: offer.lender,
+        collection: offer.collection,
+        loan_amount: offer.loan_amount,
+        interest_rate: offer.interest_rate,
+        duration: offer.duration,
+        loan_count: params.loan_count,
+    });
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct TakeLoanOffer<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    #[account(
+        seeds = [
+            b"collection",
+            offer.collection.as_ref()
+        ],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    /// CHECK: Floor price feed of the collection, validated in oracle::load_price
+    pub price_feed: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"offer",
+            offer.lender.as_ref(),
+            offer.nonce.to_le_bytes().as_ref()
+        ],
+        bump = offer.bump
+    )]
+    pub offer: Box<Account<'info, LoanOffer>>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"offer_escrow",
+            offer.key().as_ref()
+        ],
+        bump
+    )]
+    pub offer_usdc: Box<Account<'info, TokenAccount>>,
+    
+    // pNFT accounts
+    pub collateral_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
+    #[account(
+        mut,
+        constraint = borrower_token.mint == collateral_mint.key(),
+        constraint = borrower_token.owner == borrower.key()
+    )]
+    pub borrower_token: Box<Account<'info, TokenAccount>>,
+    
+    #[account(address = offer.loan_mint)]
+    pub loan_mint: Box<Account<'info, Mint>>,
+    
+    // Principal token accounts
+    #[account(
+        mut,
+        constraint = borrower_usdc.mint == loan_mint.key(),
+        constraint = borrower_usdc.owner == borrower.key()
+    )]
+    pub borrower_usdc: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = treasury_usdc.mint == loan_mint.key(),
+        constraint = treasury_usdc.owner == protocol.treasury
+    )]
+    pub treasury_usdc: Box<Account<'info, TokenAccount>>,
+    
+    // Loan PDA
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Loan::LEN,
+        seeds = [
+            b"loan",
+            borrower.key().as_ref(),
+            collateral_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub loan: Box<Account<'info, Loan>>,
+    
+    // Vault to hold collateral
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Vault::LEN,
+        seeds = [
+            b"vault",
+            loan.key().as_ref()
+        ],
+        bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+    
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_token: Box<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Token record of borrower_token, validated by Token Metadata
+    #[account(mut)]
+    pub borrower_token_record: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token record of vault_token, validated by Token Metadata
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn take_handler(ctx: Context<TakeLoanOffer>, expected_terms: LoanTerms) -> Result<()> {
+    let offer = &ctx.accounts.offer;
+    require!(offer.loans_remaining > 0, LoanError::InsufficientLoanAmount);
+    
+    // The offer address only depends on the lender and nonce, so it can be
+    // cancelled and re-posted on other terms while a take is in flight
+    require!(offer.terms() == expected_terms, LoanError::LoanTermsMismatch);
+    
+    let metadata = validate_collateral(
+        &ctx.accounts.protocol,
+        &ctx.accounts.collateral_mint,
+        &ctx.accounts.pnft.collateral_metadata,
+        &ctx.accounts.pnft.collateral_edition,
+    )?;
+    let collection_config = &ctx.accounts.collection_config;
+    validate_collection(&metadata, collection_config)?;
+    require!(offer.duration <= collection_config.max_duration, LoanError::InvalidLoanDuration);
+    validate_loan_terms(
+        &ctx.accounts.protocol,
+        &offer.loan_mint,
+        offer.loan_amount,
+        offer.duration,
+        offer.interest_rate,
+    )?;
+    check_max_ltv(
+        collection_config,
+        &ctx.accounts.price_feed,
+        ctx.accounts.loan_mint.decimals,
+        offer.loan_amount,
+    )?;
+    
+    // Escrow pNFT in the vault
+    ctx.accounts.pnft.transfer(
+        &ctx.accounts.collateral_mint,
+        PnftHolder::new(ctx.accounts.borrower_token.to_account_info(), ctx.accounts.borrower.to_account_info(), &ctx.accounts.borrower_token_record),
+        PnftHolder::new(ctx.accounts.vault_token.to_account_info(), ctx.accounts.vault.to_account_info(), &ctx.accounts.vault_token_record),
+        ctx.accounts.borrower.to_account_info(),
+        ctx.accounts.borrower.to_account_info(),
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.associated_token_program,
+    )
+    .invoke()?;
+    
+    // Disburse principal from the offer escrow
+    let nonce_bytes = offer.nonce.to_le_bytes();
+    let offer_seeds: &[&[&[u8]]] = &[&[b"offer", offer.lender.as_ref(), &nonce_bytes, &[offer.bump]]];
+    
+    PrincipalDisbursement {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        source: ctx.accounts.offer_usdc.to_account_info(),
+        authority: ctx.accounts.offer.to_account_info(),
+        borrower_usdc: ctx.accounts.borrower_usdc.to_account_info(),
+        treasury_usdc: ctx.accounts.treasury_usdc.to_account_info(),
+    }
+    .invoke_signed(offer.loan_amount, ctx.accounts.protocol.fee_rate, offer_seeds)?;
+    
+    ctx.accounts.vault_token.reload()?;
+    require!(ctx.accounts.vault_token.amount == 1, LoanError::CollateralNotInVault);
+    
+    // Loan on the offer's terms
+    let offer = &ctx.accounts.offer;
+    init_loan(
+        &mut ctx.accounts.loan,
+        ctx.bumps.loan,
+        Origination {
+            borrower: ctx.accounts.borrower.key(),
+            lender: offer.lender,
+            collateral_mint: ctx.accounts.collateral_mint.key(),
+            loan_mint: offer.loan_mint,
+            terms: offer.terms(),
+            status: LoanStatus::Active,
+            pool_funded: false,
+        },
+        &ctx.accounts.collection_config,
+        &mut ctx.accounts.protocol,
+    )?;
+    init_vault(&mut ctx.accounts.vault, ctx.bumps.vault, &ctx.accounts.loan)?;
+    
+    let loan = &ctx.accounts.loan;
+    let offer = &mut ctx.accounts.offer;
+    offer.loans_remaining -= 1;
+    
+    emit!(OfferTaken {
+        offer: offer.key(),
+        loan: loan.key(),
+        borrower: loan.borrower,
+        loans_remaining: offer.loans_remaining,
+    });
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelLoanOffer<'info> {
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"offer",
+            lender.key().as_ref(),
+            offer.nonce.to_le_bytes().as_ref()
+        ],
+        bump = offer.bump,
+        has_one = lender,
+        close = lender
+    )]
+    pub offer: Account<'info, LoanOffer>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"offer_escrow",
+            offer.key().as_ref()
+        ],
+        bump
+    )]
+    pub offer_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = lender_usdc.mint == offer.loan_mint,
+        constraint = lender_usdc.owner == lender.key()
+    )]
+    pub lender_usdc: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn cancel_handler(ctx: Context<CancelLoanOffer>) -> Result<()> {
+    let offer = &ctx.accounts.offer;
+    let nonce_bytes = offer.nonce.to_le_bytes();
+    let offer_seeds: &[&[&[u8]]] = &[&[b"offer", offer.lender.as_ref(), &nonce_bytes, &[offer.bump]]];
+    
+    // Return the unused principal and close the escrow
+    let refunded_amount = ctx.accounts.offer_usdc.amount;
+    
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.offer_usdc.to_account_info(),
+            to: ctx.accounts.lender_usdc.to_account_info(),
+            authority: ctx.accounts.offer.to_account_info(),
+        },
+        offer_seeds,
+    );
+    
+    token::transfer(transfer_ctx, refunded_amount)?;
+    
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.offer_usdc.to_account_info(),
+            destination: ctx.accounts.lender.to_account_info(),
+            authority: ctx.accounts.offer.to_account_info(),
+        },
+        offer_seeds,
+    );
+    
+    token::close_account(close_ctx)?;
+    
+    emit!(OfferCancelled {
+        offer: offer.key(),
+        lender: offer.lender,
+        refunded_amount,
+    });
+    
+    Ok(())
+}
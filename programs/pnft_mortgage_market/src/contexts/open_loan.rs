@@ -0,0 +1,199 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount, Mint};
+use anchor_spl::associated_token::AssociatedToken;
+
+use crate::states::*;
+use crate::errors::*;
+use crate::contexts::create_loan::{check_max_ltv, init_loan, init_vault, validate_loan_terms, Origination, PrincipalDisbursement};
+use crate::pnft::*;
+
+#[derive(Accounts)]
+#[instruction(loan_amount: u64, duration: i64, interest_rate: u16)]
+pub struct OpenLoan<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    #[account(
+        seeds = [
+            b"collection",
+            collection_config.collection_mint.as_ref()
+        ],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    /// CHECK: Floor price feed of the collection, validated in oracle::load_price
+    pub price_feed: UncheckedAccount<'info>,
+    
+    // pNFT accounts
+    pub collateral_mint: Account<'info, Mint>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
+    #[account(
+        mut,
+        constraint = borrower_token.mint == collateral_mint.key(),
+        constraint = borrower_token.owner == borrower.key()
+    )]
+    pub borrower_token: Account<'info, TokenAccount>,
+    
+    // Mint the principal is denominated in
+    pub loan_mint: Account<'info, Mint>,
+    
+    // Principal token accounts
+    #[account(
+        mut,
+        constraint = lender_usdc.mint == loan_mint.key(),
+        constraint = lender_usdc.owner == lender.key()
+    )]
+    pub lender_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = borrower_usdc.mint == loan_mint.key(),
+        constraint = borrower_usdc.owner == borrower.key()
+    )]
+    pub borrower_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury_usdc.mint == loan_mint.key(),
+        constraint = treasury_usdc.owner == protocol.treasury
+    )]
+    pub treasury_usdc: Account<'info, TokenAccount>,
+    
+    // Loan PDA
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Loan::LEN,
+        seeds = [
+            b"loan",
+            borrower.key().as_ref(),
+            collateral_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub loan: Account<'info, Loan>,
+    
+    // Vault to hold collateral
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Vault::LEN,
+        seeds = [
+            b"vault",
+            loan.key().as_ref()
+        ],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_token: Account<'info, TokenAccount>,
+    
+    /// CHECK: Token record of borrower_token, validated by Token Metadata
+    #[account(mut)]
+    pub borrower_token_record: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token record of vault_token, validated by Token Metadata
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn handler(
+    ctx: Context<OpenLoan>,
+    loan_amount: u64,
+    duration: i64,
+    interest_rate: u16,
+    schedule: RepaymentSchedule,
+) -> Result<()> {
+    let metadata = validate_collateral(
+        &ctx.accounts.protocol,
+        &ctx.accounts.collateral_mint,
+        &ctx.accounts.pnft.collateral_metadata,
+        &ctx.accounts.pnft.collateral_edition,
+    )?;
+    let collection_config = &ctx.accounts.collection_config;
+    validate_collection(&metadata, collection_config)?;
+    require!(duration <= collection_config.max_duration, LoanError::InvalidLoanDuration);
+    validate_loan_terms(
+        &ctx.accounts.protocol,
+        &ctx.accounts.loan_mint.key(),
+        loan_amount,
+        duration,
+        interest_rate,
+    )?;
+    check_max_ltv(
+        collection_config,
+        &ctx.accounts.price_feed,
+        ctx.accounts.loan_mint.decimals,
+        loan_amount,
+    )?;
+    
+    // Escrow pNFT in the vault
+    ctx.accounts.pnft.transfer(
+        &ctx.accounts.collateral_mint,
+        PnftHolder::new(ctx.accounts.borrower_token.to_account_info(), ctx.accounts.borrower.to_account_info(), &ctx.accounts.borrower_token_record),
+        PnftHolder::new(ctx.accounts.vault_token.to_account_info(), ctx.accounts.vault.to_account_info(), &ctx.accounts.vault_token_record),
+        ctx.accounts.borrower.to_account_info(),
+        ctx.accounts.borrower.to_account_info(),
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.associated_token_program,
+    )
+    .invoke()?;
+    
+    PrincipalDisbursement {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        source: ctx.accounts.lender_usdc.to_account_info(),
+        authority: ctx.accounts.lender.to_account_info(),
+        borrower_usdc: ctx.accounts.borrower_usdc.to_account_info(),
+        treasury_usdc: ctx.accounts.treasury_usdc.to_account_info(),
+    }
+    .invoke(loan_amount, ctx.accounts.protocol.fee_rate)?;
+    
+    ctx.accounts.vault_token.reload()?;
+    require!(ctx.accounts.vault_token.amount == 1, LoanError::CollateralNotInVault);
+    
+    init_loan(
+        &mut ctx.accounts.loan,
+        ctx.bumps.loan,
+        Origination {
+            borrower: ctx.accounts.borrower.key(),
+            lender: ctx.accounts.lender.key(),
+            collateral_mint: ctx.accounts.collateral_mint.key(),
+            loan_mint: ctx.accounts.loan_mint.key(),
+            terms: LoanTerms { loan_amount, interest_rate, duration, schedule },
+            status: LoanStatus::Active,
+            pool_funded: false,
+        },
+        &ctx.accounts.collection_config,
+        &mut ctx.accounts.protocol,
+    )?;
+    init_vault(&mut ctx.accounts.vault, ctx.bumps.vault, &ctx.accounts.loan)?;
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount, Transfer};
+
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::contexts::pool::record_pool_repayment;
+use crate::contexts::repay_loan::collect_late_fee;
+
+#[derive(Accounts)]
+pub struct PayInstallment<'info> {
+    pub borrower: Signer<'info>,
+    
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    #[account(
+        mut,
+        constraint = loan.borrower == borrower.key(),
+        constraint = loan.status == LoanStatus::Active @ LoanError::LoanNotActive
+    )]
+    pub loan: Account<'info, Loan>,
+    
+    // USDC token accounts for repayment
+    #[account(
+        mut,
+        constraint = borrower_usdc.mint == loan.loan_mint,
+        constraint = borrower_usdc.owner == borrower.key()
+    )]
+    pub borrower_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = lender_usdc.mint == loan.loan_mint,
+        constraint = lender_usdc.owner == loan.lender
+    )]
+    pub lender_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury_usdc.mint == loan.loan_mint,
+        constraint = treasury_usdc.owner == protocol.treasury
+    )]
+    pub treasury_usdc: Account<'info, TokenAccount>,
+    
+    // Lending pool that funded the loan, required when loan.pool_funded
+    #[account(
+        mut,
+        constraint = pool.key() == loan.lender @ LoanError::InvalidPool
+    )]
+    pub pool: Option<Account<'info, LendingPool>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<PayInstallment>) -> Result<()> {
+    let loan = &mut ctx.accounts.loan;
+    let clock = Clock::get()?;
+    
+    let period = match loan.schedule {
+        RepaymentSchedule::Amortizing { period } => period,
+        RepaymentSchedule::Bullet => return err!(LoanError::InvalidRepaymentSchedule),
+    };
+    
+    loan.accrue_interest(clock.unix_timestamp)?;
+    
+    // The final installment settles the balance through repay_loan, which releases the collateral
+    let amount = loan.installment_amount;
+    require!(
+        amount > 0 && amount < loan.outstanding_amount,
+        LoanError::InvalidRepaymentAmount
+    );
+    
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.borrower_usdc.to_account_info(),
+            to: ctx.accounts.lender_usdc.to_account_info(),
+            authority: ctx.accounts.borrower.to_account_info(),
+        },
+    );
+    
+    anchor_spl::token::transfer(transfer_ctx, amount)?;
+    
+    let late_fee = loan.late_fee_policy.late_fee(amount, loan.next_due_time, clock.unix_timestamp)?;
+    collect_late_fee(
+        &ctx.accounts.token_program,
+        &ctx.accounts.borrower,
+        &ctx.accounts.borrower_usdc,
+        &ctx.accounts.lender_usdc,
+        &ctx.accounts.treasury_usdc,
+        loan,
+        late_fee,
+    )?;
+    
+    let (_, principal_paid) = loan.apply_payment(amount);
+    record_pool_repayment(loan, ctx.accounts.pool.as_mut(), &ctx.accounts.lender_usdc.key(), principal_paid)?;
+    
+    let late = clock.unix_timestamp > loan.next_due_time;
+    if late {
+        loan.late_payments = loan.late_payments.checked_add(1).ok_or(LoanError::MathOverflow)?;
+    }
+    loan.installments_paid = loan.installments_paid.checked_add(1).ok_or(LoanError::MathOverflow)?;
+    loan.next_due_time = loan.next_due_time.checked_add(period).ok_or(LoanError::MathOverflow)?;
+    
+    emit!(InstallmentPaid {
+        loan: loan.key(),
+        borrower: loan.borrower,
+        installment: loan.installments_paid,
+        amount,
+        late,
+        remaining_balance: loan.outstanding_amount,
+        next_due_time: loan.next_due_time,
+    });
+    
+    Ok(())
+}
@@ -0,0 +1,538 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, MintTo, Token, TokenAccount, Mint, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::utils::checked_add;
+use crate::contexts::create_loan::{check_max_ltv, init_loan, init_vault, validate_loan_terms, Origination, PrincipalDisbursement};
+use crate::pnft::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LendingPoolParams {
+    pub collections: Vec<Pubkey>,   // eligible collections
+    pub rate_model: InterestRateModel,
+    pub duration: i64,              // seconds
+    pub max_loan_amount: u64,       // principal cap per loan
+    pub schedule: RepaymentSchedule,
+}
+
+impl LendingPoolParams {
+    fn apply(&self, pool: &mut LendingPool) -> Result<()> {
+        require!(
+            self.collections.len() <= LendingPool::MAX_COLLECTIONS
+                && self.rate_model.is_valid()
+                && self.duration > 0
+                && self.max_loan_amount > 0,
+            LoanError::InvalidPool
+        );
+        self.schedule.installment_count(self.duration)?;
+        
+        pool.collections = self.collections.clone();
+        pool.rate_model = self.rate_model;
+        pool.duration = self.duration;
+        pool.max_loan_amount = self.max_loan_amount;
+        pool.schedule = self.schedule;
+        
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(pool_id: u64)]
+pub struct CreateLendingPool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        has_one = authority
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    pub loan_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LendingPool::LEN,
+        seeds = [
+            b"pool",
+            loan_mint.key().as_ref(),
+            pool_id.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub pool: Account<'info, LendingPool>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [
+            b"pool_shares",
+            pool.key().as_ref()
+        ],
+        bump,
+        mint::decimals = loan_mint.decimals,
+        mint::authority = pool
+    )]
+    pub share_mint: Account<'info, Mint>,
+    
+    // Pool liquidity: a program-derived token account owned by the pool PDA,
+    // so it cannot be created ahead of the pool
+    #[account(
+        init,
+        payer = authority,
+        seeds = [
+            b"pool_liquidity",
+            pool.key().as_ref()
+        ],
+        bump,
+        token::mint = loan_mint,
+        token::authority = pool
+    )]
+    pub pool_usdc: Account<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn create_handler(
+    ctx: Context<CreateLendingPool>,
+    pool_id: u64,
+    params: LendingPoolParams,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    
+    pool.authority = ctx.accounts.authority.key();
+    pool.loan_mint = ctx.accounts.loan_mint.key();
+    pool.share_mint = ctx.accounts.share_mint.key();
+    pool.pool_id = pool_id;
+    pool.total_borrowed = 0;
+    pool.bump = ctx.bumps.pool;
+    params.apply(pool)?;
+    
+    emit!(PoolCreated {
+        pool: pool.key(),
+        loan_mint: pool.loan_mint,
+        share_mint: pool.share_mint,
+    });
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateLendingPool<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.loan_mint.as_ref(),
+            pool.pool_id.to_le_bytes().as_ref()
+        ],
+        bump = pool.bump,
+        has_one = authority
+    )]
+    pub pool: Account<'info, LendingPool>,
+}
+
+pub fn update_handler(ctx: Context<UpdateLendingPool>, params: LendingPoolParams) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    
+    params.apply(pool)?;
+    
+    msg!("Lending pool updated: {}", pool.key());
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PoolLiquidity<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    #[account(
+        seeds = [
+            b"pool",
+            pool.loan_mint.as_ref(),
+            pool.pool_id.to_le_bytes().as_ref()
+        ],
+        bump = pool.bump,
+        has_one = share_mint
+    )]
+    pub pool: Account<'info, LendingPool>,
+    
+    #[account(mut)]
+    pub share_mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"pool_liquidity",
+            pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub pool_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = depositor_usdc.mint == pool.loan_mint,
+        constraint = depositor_usdc.owner == depositor.key()
+    )]
+    pub depositor_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        associated_token::mint = share_mint,
+        associated_token::authority = depositor
+    )]
+    pub depositor_shares: Account<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn deposit_handler(ctx: Context<PoolLiquidity>, amount: u64) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let total_assets = pool.total_assets(ctx.accounts.pool_usdc.amount)?;
+    let shares = pool.shares_for_deposit(amount, total_assets, ctx.accounts.share_mint.supply)?;
+    require!(shares > 0, LoanError::PoolAmountTooSmall);
+    
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.depositor_usdc.to_account_info(),
+            to: ctx.accounts.pool_usdc.to_account_info(),
+            authority: ctx.accounts.depositor.to_account_info(),
+        },
+    );
+    
+    token::transfer(transfer_ctx, amount)?;
+    
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_seeds: &[&[&[u8]]] = &[&[b"pool", pool.loan_mint.as_ref(), &pool_id_bytes, &[pool.bump]]];
+    
+    let mint_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        MintTo {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            to: ctx.accounts.depositor_shares.to_account_info(),
+            authority: ctx.accounts.pool.to_account_info(),
+        },
+        pool_seeds,
+    );
+    
+    token::mint_to(mint_ctx, shares)?;
+    
+    emit!(PoolDeposited {
+        pool: pool.key(),
+        depositor: ctx.accounts.depositor.key(),
+        amount,
+        shares,
+    });
+    
+    Ok(())
+}
+
+pub fn withdraw_handler(ctx: Context<PoolLiquidity>, shares: u64) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let total_assets = pool.total_assets(ctx.accounts.pool_usdc.amount)?;
+    let amount = pool.amount_for_shares(shares, total_assets, ctx.accounts.share_mint.supply)?;
+    require!(amount > 0, LoanError::PoolAmountTooSmall);
+    
+    // Only idle liquidity can leave the pool
+    require!(amount <= ctx.accounts.pool_usdc.amount, LoanError::InsufficientLiquidity);
+    
+    let burn_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Burn {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            from: ctx.accounts.depositor_shares.to_account_info(),
+            authority: ctx.accounts.depositor.to_account_info(),
+        },
+    );
+    
+    token::burn(burn_ctx, shares)?;
+    
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_seeds: &[&[&[u8]]] = &[&[b"pool", pool.loan_mint.as_ref(), &pool_id_bytes, &[pool.bump]]];
+    
+    let transfer_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.pool_usdc.to_account_info(),
+            to: ctx.accounts.depositor_usdc.to_account_info(),
+            authority: ctx.accounts.pool.to_account_info(),
+        },
+        pool_seeds,
+    );
+    
+    token::transfer(transfer_ctx, amount)?;
+    
+    emit!(PoolWithdrawn {
+        pool: pool.key(),
+        depositor: ctx.accounts.depositor.key(),
+        amount,
+        shares,
+    });
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct BorrowFromPool<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Box<Account<'info, Protocol>>,
+    
+    #[account(
+        seeds = [
+            b"collection",
+            collection_config.collection_mint.as_ref()
+        ],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Box<Account<'info, CollectionConfig>>,
+    
+    /// CHECK: Floor price feed of the collection, validated in oracle::load_price
+    pub price_feed: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.loan_mint.as_ref(),
+            pool.pool_id.to_le_bytes().as_ref()
+        ],
+        bump = pool.bump
+    )]
+    pub pool: Box<Account<'info, LendingPool>>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"pool_liquidity",
+            pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub pool_usdc: Box<Account<'info, TokenAccount>>,
+    
+    // pNFT accounts
+    pub collateral_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
+    #[account(
+        mut,
+        constraint = borrower_token.mint == collateral_mint.key(),
+        constraint = borrower_token.owner == borrower.key()
+    )]
+    pub borrower_token: Box<Account<'info, TokenAccount>>,
+    
+    #[account(address = pool.loan_mint)]
+    pub loan_mint: Box<Account<'info, Mint>>,
+    
+    // Principal token accounts
+    #[account(
+        mut,
+        constraint = borrower_usdc.mint == loan_mint.key(),
+        constraint = borrower_usdc.owner == borrower.key()
+    )]
+    pub borrower_usdc: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = treasury_usdc.mint == loan_mint.key(),
+        constraint = treasury_usdc.owner == protocol.treasury
+    )]
+    pub treasury_usdc: Box<Account<'info, TokenAccount>>,
+    
+    // Loan PDA
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Loan::LEN,
+        seeds = [
+            b"loan",
+            borrower.key().as_ref(),
+            collateral_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub loan: Box<Account<'info, Loan>>,
+    
+    // Vault to hold collateral
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Vault::LEN,
+        seeds = [
+            b"vault",
+            loan.key().as_ref()
+        ],
+        bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+    
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_token: Box<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Token record of borrower_token, validated by Token Metadata
+    #[account(mut)]
+    pub borrower_token_record: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token record of vault_token, validated by Token Metadata
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn borrow_handler(ctx: Context<BorrowFromPool>, loan_amount: u64) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let collection_config = &ctx.accounts.collection_config;
+    require!(
+        pool.accepts_collection(&collection_config.collection_mint),
+        LoanError::CollectionNotEligible
+    );
+    require!(loan_amount <= pool.max_loan_amount, LoanError::InsufficientLoanAmount);
+    require!(loan_amount <= ctx.accounts.pool_usdc.amount, LoanError::InsufficientLiquidity);
+    
+    // Price the loan off the pool's utilization before this loan
+    let interest_rate = pool.current_borrow_rate(ctx.accounts.pool_usdc.amount)?;
+    
+    let metadata = validate_collateral(
+        &ctx.accounts.protocol,
+        &ctx.accounts.collateral_mint,
+        &ctx.accounts.pnft.collateral_metadata,
+        &ctx.accounts.pnft.collateral_edition,
+    )?;
+    validate_collection(&metadata, collection_config)?;
+    require!(pool.duration <= collection_config.max_duration, LoanError::InvalidLoanDuration);
+    validate_loan_terms(
+        &ctx.accounts.protocol,
+        &pool.loan_mint,
+        loan_amount,
+        pool.duration,
+        interest_rate,
+    )?;
+    check_max_ltv(
+        collection_config,
+        &ctx.accounts.price_feed,
+        ctx.accounts.loan_mint.decimals,
+        loan_amount,
+    )?;
+    
+    // Escrow pNFT in the vault
+    ctx.accounts.pnft.transfer(
+        &ctx.accounts.collateral_mint,
+        PnftHolder::new(ctx.accounts.borrower_token.to_account_info(), ctx.accounts.borrower.to_account_info(), &ctx.accounts.borrower_token_record),
+        PnftHolder::new(ctx.accounts.vault_token.to_account_info(), ctx.accounts.vault.to_account_info(), &ctx.accounts.vault_token_record),
+        ctx.accounts.borrower.to_account_info(),
+        ctx.accounts.borrower.to_account_info(),
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.associated_token_program,
+    )
+    .invoke()?;
+    
+    // Disburse principal from pool liquidity
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_seeds: &[&[&[u8]]] = &[&[b"pool", pool.loan_mint.as_ref(), &pool_id_bytes, &[pool.bump]]];
+    
+    PrincipalDisbursement {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        source: ctx.accounts.pool_usdc.to_account_info(),
+        authority: ctx.accounts.pool.to_account_info(),
+        borrower_usdc: ctx.accounts.borrower_usdc.to_account_info(),
+        treasury_usdc: ctx.accounts.treasury_usdc.to_account_info(),
+    }
+    .invoke_signed(loan_amount, ctx.accounts.protocol.fee_rate, pool_seeds)?;
+    
+    ctx.accounts.vault_token.reload()?;
+    require!(ctx.accounts.vault_token.amount == 1, LoanError::CollateralNotInVault);
+    
+    // Loan on the pool's terms
+    let pool = &ctx.accounts.pool;
+    init_loan(
+        &mut ctx.accounts.loan,
+        ctx.bumps.loan,
+        Origination {
+            borrower: ctx.accounts.borrower.key(),
+            lender: pool.key(),
+            collateral_mint: ctx.accounts.collateral_mint.key(),
+            loan_mint: pool.loan_mint,
+            terms: LoanTerms {
+                loan_amount,
+                interest_rate,
+                duration: pool.duration,
+                schedule: pool.schedule,
+            },
+            status: LoanStatus::Active,
+            pool_funded: true,
+        },
+        &ctx.accounts.collection_config,
+        &mut ctx.accounts.protocol,
+    )?;
+    init_vault(&mut ctx.accounts.vault, ctx.bumps.vault, &ctx.accounts.loan)?;
+    
+    let pool = &mut ctx.accounts.pool;
+    pool.total_borrowed = checked_add(pool.total_borrowed, loan_amount)?;
+    
+    Ok(())
+}
+
+// Keep pool accounting in step with principal repaid on pool-funded loans;
+// the payment must have gone to the pool's liquidity account
+pub fn record_pool_repayment(
+    loan: &Loan,
+    pool: Option<&mut Account<LendingPool>>,
+    lender_usdc: &Pubkey,
+    principal_paid: u64,
+) -> Result<()> {
+    if !loan.pool_funded {
+        return Ok(());
+    }
+    
+    let pool = pool.ok_or(LoanError::InvalidPool)?;
+    require_keys_eq!(pool.key(), loan.lender, LoanError::InvalidPool);
+    require_keys_eq!(*lender_usdc, LendingPool::liquidity_address(&pool.key()), LoanError::InvalidPool);
+    pool.total_borrowed = pool.total_borrowed.saturating_sub(principal_paid);
+    
+    Ok(())
+}
+
+// A liquidated pool loan's remaining principal leaves total_borrowed in full:
+// whatever the auction recovered is already in the pool's liquidity, and the
+// rest is written off as the LPs' loss
+pub fn record_pool_write_off(
+    loan: &Loan,
+    pool: Option<&mut Account<LendingPool>>,
+    lender_usdc: &Pubkey,
+) -> Result<()> {
+    record_pool_repayment(loan, pool, lender_usdc, loan.outstanding_principal())
+}
@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount, Mint, Transfer};
+
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::utils::checked_add;
+use crate::contexts::create_loan::{check_max_ltv, setup_schedule, validate_loan_terms};
+use crate::contexts::pool::record_pool_repayment;
+
+#[derive(Accounts)]
+pub struct RefinanceLoan<'info> {
+    pub borrower: Signer<'info>,
+    
+    #[account(mut)]
+    pub new_lender: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    #[account(
+        seeds = [
+            b"collection",
+            loan.collection.as_ref()
+        ],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    /// CHECK: Floor price feed of the collection, validated in oracle::load_price
+    pub price_feed: UncheckedAccount<'info>,
+    
+    // Collateral stays in the vault; only the lender and terms change
+    #[account(
+        mut,
+        constraint = loan.borrower == borrower.key(),
+        constraint = loan.status == LoanStatus::Active @ LoanError::LoanNotActive
+    )]
+    pub loan: Account<'info, Loan>,
+    
+    #[account(address = loan.loan_mint)]
+    pub loan_mint: Account<'info, Mint>,
+    
+    // USDC token accounts for the payoff
+    #[account(
+        mut,
+        constraint = new_lender_usdc.mint == loan.loan_mint,
+        constraint = new_lender_usdc.owner == new_lender.key()
+    )]
+    pub new_lender_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = old_lender_usdc.mint == loan.loan_mint,
+        constraint = old_lender_usdc.owner == loan.lender
+    )]
+    pub old_lender_usdc: Account<'info, TokenAccount>,
+    
+    // Lending pool being paid off, required when loan.pool_funded
+    #[account(
+        mut,
+        constraint = pool.key() == loan.lender @ LoanError::InvalidPool
+    )]
+    pub pool: Option<Account<'info, LendingPool>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(
+    ctx: Context<RefinanceLoan>,
+    duration: i64,
+    interest_rate: u16,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let loan = &mut ctx.accounts.loan;
+    
+    // Pay off the old lender in full: principal + interest accrued to now
+    loan.accrue_interest(clock.unix_timestamp)?;
+    let payoff_amount = loan.outstanding_amount;
+    
+    require!(
+        duration <= ctx.accounts.collection_config.max_duration,
+        LoanError::InvalidLoanDuration
+    );
+    validate_loan_terms(&ctx.accounts.protocol, &loan.loan_mint, payoff_amount, duration, interest_rate)?;
+    check_max_ltv(
+        &ctx.accounts.collection_config,
+        &ctx.accounts.price_feed,
+        ctx.accounts.loan_mint.decimals,
+        payoff_amount,
+    )?;
+    
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.new_lender_usdc.to_account_info(),
+            to: ctx.accounts.old_lender_usdc.to_account_info(),
+            authority: ctx.accounts.new_lender.to_account_info(),
+        },
+    );
+    
+    anchor_spl::token::transfer(transfer_ctx, payoff_amount)?;
+    
+    // The old lender is repaid in full; a pool gets its principal back
+    record_pool_repayment(
+        loan,
+        ctx.accounts.pool.as_mut(),
+        &ctx.accounts.old_lender_usdc.key(),
+        loan.outstanding_principal(),
+    )?;
+    
+    // The payoff becomes the principal of the new loan
+    let old_lender = loan.lender;
+    loan.lender = ctx.accounts.new_lender.key();
+    loan.loan_amount = payoff_amount;
+    loan.outstanding_amount = payoff_amount;
+    loan.accrued_interest = 0;
+    loan.pool_funded = false;
+    loan.interest_rate = interest_rate;
+    loan.duration = duration;
+    loan.start_time = clock.unix_timestamp;
+    loan.last_accrual_time = clock.unix_timestamp;
+    let schedule = loan.schedule;
+    setup_schedule(loan, schedule)?;
+    
+    ctx.accounts.protocol.total_volume = checked_add(ctx.accounts.protocol.total_volume, payoff_amount)?;
+    
+    emit!(LoanRefinanced {
+        loan: loan.key(),
+        borrower: loan.borrower,
+        old_lender,
+        new_lender: loan.lender,
+        payoff_amount,
+        duration,
+        interest_rate,
+    });
+    
+    Ok(())
+}
@@ -1,31 +1,54 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount, Transfer};
+use anchor_spl::token::{CloseAccount, Token, TokenAccount, Mint, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
 
 use crate::states::*;
+use crate::errors::*;
 use crate::events::*;
+use crate::utils::checked_sub;
+use crate::contexts::pool::record_pool_repayment;
+use crate::pnft::*;
 
 #[derive(Accounts)]
 pub struct RepayLoan<'info> {
     #[account(mut)]
     pub borrower: Signer<'info>,
     
-    #[account(mut)]
-    pub lender: Signer<'info>,
+    /// CHECK: Lender receiving the repayment
+    #[account(address = loan.lender)]
+    pub lender: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, Protocol>,
     
+    // Loan and vault are closed on repayment so the pNFT can back a new loan
     #[account(
         mut,
+        close = borrower,
         constraint = loan.borrower == borrower.key(),
-        constraint = loan.lender == lender.key(),
         constraint = loan.status == LoanStatus::Active
     )]
     pub loan: Account<'info, Loan>,
     
     #[account(
         mut,
+        close = borrower,
         constraint = vault.loan == loan.key()
     )]
     pub vault: Account<'info, Vault>,
     
+    // pNFT accounts
+    #[account(address = loan.collateral_mint)]
+    pub collateral_mint: Account<'info, Mint>,
+    
+    #[account(
+        constraint = pnft.is_collateral(&collateral_mint.key()) @ LoanError::InvalidCollateralMetadata
+    )]
+    pub pnft: PnftAccounts<'info>,
+    
     #[account(
         mut,
         constraint = borrower_token.mint == loan.collateral_mint,
@@ -43,26 +66,52 @@ pub struct RepayLoan<'info> {
     // USDC token accounts for repayment
     #[account(
         mut,
+        constraint = borrower_usdc.mint == loan.loan_mint,
         constraint = borrower_usdc.owner == borrower.key()
     )]
     pub borrower_usdc: Account<'info, TokenAccount>,
     
     #[account(
         mut,
+        constraint = lender_usdc.mint == loan.loan_mint,
         constraint = lender_usdc.owner == lender.key()
     )]
     pub lender_usdc: Account<'info, TokenAccount>,
     
+    #[account(
+        mut,
+        constraint = treasury_usdc.mint == loan.loan_mint,
+        constraint = treasury_usdc.owner == protocol.treasury
+    )]
+    pub treasury_usdc: Account<'info, TokenAccount>,
+    
+    /// CHECK: Token record of vault_token, validated by Token Metadata
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token record of borrower_token, validated by Token Metadata
+    #[account(mut)]
+    pub borrower_token_record: Option<UncheckedAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+    // Lending pool that funded the loan, required when loan.pool_funded
+    #[account(
+        mut,
+        constraint = pool.key() == loan.lender @ LoanError::InvalidPool
+    )]
+    pub pool: Option<Account<'info, LendingPool>>,
+    
     pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 pub fn handler(ctx: Context<RepayLoan>) -> Result<()> {
-    let loan = &mut ctx.accounts.loan;
     let clock = Clock::get()?;
     
-    // Calculate total repayment amount (principal + interest)
-    let interest = loan.calculate_interest(clock.unix_timestamp);
-    let total_repayment = loan.loan_amount + interest;
+    // Accrue interest to now; the balance is principal + interest
+    ctx.accounts.loan.accrue_interest(clock.unix_timestamp)?;
+    let loan = &ctx.accounts.loan;
+    let total_repayment = loan.outstanding_amount;
     
     // Transfer USDC from borrower to lender
     let transfer_ctx = CpiContext::new(
@@ -76,21 +125,73 @@ pub fn handler(ctx: Context<RepayLoan>) -> Result<()> {
     
     anchor_spl::token::transfer(transfer_ctx, total_repayment)?;
     
-    // Transfer NFT back to borrower
-    let transfer_nft_ctx = CpiContext::new(
+    // Repaying past the due time costs a late fee on the overdue part of the balance,
+    // split between lender and treasury
+    let late_fee = loan.late_fee_policy.late_fee(
+        loan.overdue_amount(clock.unix_timestamp),
+        loan.next_due_time,
+        clock.unix_timestamp,
+    )?;
+    collect_late_fee(
+        &ctx.accounts.token_program,
+        &ctx.accounts.borrower,
+        &ctx.accounts.borrower_usdc,
+        &ctx.accounts.lender_usdc,
+        &ctx.accounts.treasury_usdc,
+        loan,
+        late_fee,
+    )?;
+    
+    // Release pNFT back to borrower
+    let loan_key = loan.key();
+    let vault_seeds: &[&[&[u8]]] = &[&[b"vault", loan_key.as_ref(), &[ctx.accounts.vault.bump]]];
+    
+    match loan.collateral_mode {
+        CollateralMode::Escrow => {
+            ctx.accounts.pnft.transfer(
+                &ctx.accounts.collateral_mint,
+                PnftHolder::new(ctx.accounts.vault_token.to_account_info(), ctx.accounts.vault.to_account_info(), &ctx.accounts.vault_token_record),
+                PnftHolder::new(ctx.accounts.borrower_token.to_account_info(), ctx.accounts.borrower.to_account_info(), &ctx.accounts.borrower_token_record),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.borrower.to_account_info(),
+                &ctx.accounts.system_program,
+                &ctx.accounts.token_program,
+                &ctx.accounts.associated_token_program,
+            )
+            .invoke_signed(vault_seeds)?;
+        }
+        CollateralMode::DelegateLock => {
+            let lock = ctx.accounts.pnft.lock(
+                &ctx.accounts.collateral_mint,
+                PnftHolder::new(ctx.accounts.borrower_token.to_account_info(), ctx.accounts.borrower.to_account_info(), &ctx.accounts.borrower_token_record),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.borrower.to_account_info(),
+                &ctx.accounts.system_program,
+                &ctx.accounts.token_program,
+            );
+            
+            lock.unlock(vault_seeds)?;
+            lock.revoke()?;
+        }
+    }
+    
+    // The vault token account is empty now; close it with the vault
+    let close_ctx = CpiContext::new_with_signer(
         ctx.accounts.token_program.to_account_info(),
-        Transfer {
-            from: ctx.accounts.vault_token.to_account_info(),
-            to: ctx.accounts.borrower_token.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.vault_token.to_account_info(),
+            destination: ctx.accounts.borrower.to_account_info(),
             authority: ctx.accounts.vault.to_account_info(),
         },
+        vault_seeds,
     );
-    
-    anchor_spl::token::transfer(transfer_nft_ctx, 1)?;
+    anchor_spl::token::close_account(close_ctx)?;
     
     // Update loan status
+    let loan = &mut ctx.accounts.loan;
     loan.status = LoanStatus::Repaid;
-    loan.outstanding_amount = 0;
+    let (_, principal_paid) = loan.apply_payment(total_repayment);
+    record_pool_repayment(loan, ctx.accounts.pool.as_mut(), &ctx.accounts.lender_usdc.key(), principal_paid)?;
     
     emit!(LoanRepaid {
         loan: loan.key(),
@@ -100,3 +201,45 @@ pub fn handler(ctx: Context<RepayLoan>) -> Result<()> {
     
     Ok(())
 }
+
+// Charge a late fee to the borrower: treasury share to the treasury, remainder to the lender
+pub fn collect_late_fee<'info>(
+    token_program: &Program<'info, Token>,
+    borrower: &Signer<'info>,
+    borrower_usdc: &Account<'info, TokenAccount>,
+    lender_usdc: &Account<'info, TokenAccount>,
+    treasury_usdc: &Account<'info, TokenAccount>,
+    loan: &Account<'info, Loan>,
+    late_fee: u64,
+) -> Result<()> {
+    if late_fee == 0 {
+        return Ok(());
+    }
+    
+    let treasury_fee = loan.late_fee_policy.treasury_share(late_fee)?;
+    let lender_fee = checked_sub(late_fee, treasury_fee)?;
+    
+    for (destination, amount) in [(treasury_usdc, treasury_fee), (lender_usdc, lender_fee)] {
+        if amount == 0 {
+            continue;
+        }
+        let transfer_ctx = CpiContext::new(
+            token_program.to_account_info(),
+            Transfer {
+                from: borrower_usdc.to_account_info(),
+                to: destination.to_account_info(),
+                authority: borrower.to_account_info(),
+            },
+        );
+        anchor_spl::token::transfer(transfer_ctx, amount)?;
+    }
+    
+    emit!(LateFeeCharged {
+        loan: loan.key(),
+        borrower: loan.borrower,
+        amount: late_fee,
+        treasury_amount: treasury_fee,
+    });
+    
+    Ok(())
+}
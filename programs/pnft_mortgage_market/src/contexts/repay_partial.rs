@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount, Transfer};
+
+use crate::states::*;
+use crate::errors::*;
+use crate::events::*;
+use crate::contexts::pool::record_pool_repayment;
+use crate::contexts::repay_loan::collect_late_fee;
+
+#[derive(Accounts)]
+pub struct RepayPartial<'info> {
+    pub borrower: Signer<'info>,
+    
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, Protocol>,
+    
+    #[account(
+        mut,
+        constraint = loan.borrower == borrower.key(),
+        constraint = loan.status == LoanStatus::Active @ LoanError::LoanNotActive
+    )]
+    pub loan: Account<'info, Loan>,
+    
+    // USDC token accounts for repayment
+    #[account(
+        mut,
+        constraint = borrower_usdc.mint == loan.loan_mint,
+        constraint = borrower_usdc.owner == borrower.key()
+    )]
+    pub borrower_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = lender_usdc.mint == loan.loan_mint,
+        constraint = lender_usdc.owner == loan.lender
+    )]
+    pub lender_usdc: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury_usdc.mint == loan.loan_mint,
+        constraint = treasury_usdc.owner == protocol.treasury
+    )]
+    pub treasury_usdc: Account<'info, TokenAccount>,
+    
+    // Lending pool that funded the loan, required when loan.pool_funded
+    #[account(
+        mut,
+        constraint = pool.key() == loan.lender @ LoanError::InvalidPool
+    )]
+    pub pool: Option<Account<'info, LendingPool>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<RepayPartial>, amount: u64) -> Result<()> {
+    let loan = &mut ctx.accounts.loan;
+    let clock = Clock::get()?;
+    
+    loan.accrue_interest(clock.unix_timestamp)?;
+    
+    // Paying off the full balance goes through repay_loan, which releases the collateral
+    require!(
+        amount > 0 && amount < loan.outstanding_amount,
+        LoanError::InvalidRepaymentAmount
+    );
+    
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.borrower_usdc.to_account_info(),
+            to: ctx.accounts.lender_usdc.to_account_info(),
+            authority: ctx.accounts.borrower.to_account_info(),
+        },
+    );
+    
+    anchor_spl::token::transfer(transfer_ctx, amount)?;
+    
+    // The part of the payment settling an overdue balance carries a late fee
+    let late_fee = loan.late_fee_policy.late_fee(
+        amount.min(loan.overdue_amount(clock.unix_timestamp)),
+        loan.next_due_time,
+        clock.unix_timestamp,
+    )?;
+    collect_late_fee(
+        &ctx.accounts.token_program,
+        &ctx.accounts.borrower,
+        &ctx.accounts.borrower_usdc,
+        &ctx.accounts.lender_usdc,
+        &ctx.accounts.treasury_usdc,
+        loan,
+        late_fee,
+    )?;
+    
+    // Interest first, then principal
+    let (interest_paid, principal_paid) = loan.apply_payment(amount);
+    record_pool_repayment(loan, ctx.accounts.pool.as_mut(), &ctx.accounts.lender_usdc.key(), principal_paid)?;
+    
+    emit!(PartialRepayment {
+        loan: loan.key(),
+        borrower: loan.borrower,
+        amount,
+        interest_paid,
+        principal_paid,
+        remaining_balance: loan.outstanding_amount,
+    });
+    
+    Ok(())
+}
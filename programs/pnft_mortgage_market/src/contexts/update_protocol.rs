@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+
+use crate::states::{AuctionConfig, LateFeePolicy, MinPrincipal, Protocol};
+use crate::errors::*;
+use crate::interest::Compounding;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UpdateProtocolParams {
+    pub allowed_token_standards: Option<u8>, // bitmask of accepted TokenStandard values
+    pub price_updaters: Option<Vec<Pubkey>>, // keepers allowed to write floor price feeds
+    pub min_interest_rate: Option<u16>,      // APR basis points
+    pub max_interest_rate: Option<u16>,      // APR basis points
+    pub min_duration: Option<i64>,           // seconds
+    pub max_duration: Option<i64>,           // seconds
+    pub min_principals: Option<Vec<MinPrincipal>>, // replaces the per loan mint minimums
+    pub late_fee_policy: Option<LateFeePolicy>, // default grace period and late fees
+    pub compounding: Option<Compounding>,    // interest compounding of new loans
+    pub auction_config: Option<AuctionConfig>, // terms of liquidation auctions
+}
+
+#[derive(Accounts)]
+pub struct UpdateProtocol<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        has_one = authority
+    )]
+    pub protocol: Account<'info, Protocol>,
+}
+
+pub fn handler(ctx: Context<UpdateProtocol>, params: UpdateProtocolParams) -> Result<()> {
+    let protocol = &mut ctx.accounts.protocol;
+    
+    if let Some(allowed_token_standards) = params.allowed_token_standards {
+        protocol.allowed_token_standards = allowed_token_standards;
+    }
+    
+    if let Some(price_updaters) = params.price_updaters {
+        require!(
+            price_updaters.len() <= Protocol::MAX_PRICE_UPDATERS,
+            LoanError::InvalidProtocolConfig
+        );
+        protocol.price_updaters = price_updaters;
+    }
+    
+    if let Some(min_interest_rate) = params.min_interest_rate {
+        protocol.min_interest_rate = min_interest_rate;
+    }
+    
+    if let Some(max_interest_rate) = params.max_interest_rate {
+        protocol.max_interest_rate = max_interest_rate;
+    }
+    
+    if let Some(min_duration) = params.min_duration {
+        protocol.min_duration = min_duration;
+    }
+    
+    if let Some(max_duration) = params.max_duration {
+        protocol.max_duration = max_duration;
+    }
+    
+    if let Some(min_principals) = params.min_principals {
+        require!(
+            min_principals.len() <= Protocol::MAX_LOAN_MINTS,
+            LoanError::InvalidProtocolConfig
+        );
+        protocol.min_principals = min_principals;
+    }
+    
+    if let Some(late_fee_policy) = params.late_fee_policy {
+        require!(late_fee_policy.is_valid(), LoanError::InvalidProtocolConfig);
+        protocol.late_fee_policy = late_fee_policy;
+    }
+    
+    if let Some(compounding) = params.compounding {
+        require!(
+            compounding != Compounding::Periodic { periods_per_year: 0 },
+            LoanError::InvalidProtocolConfig
+        );
+        protocol.compounding = compounding;
+    }
+    
+    if let Some(auction_config) = params.auction_config {
+        require!(auction_config.is_valid(), LoanError::InvalidProtocolConfig);
+        protocol.auction_config = auction_config;
+    }
+    
+    require!(
+        protocol.min_interest_rate <= protocol.max_interest_rate,
+        LoanError::InvalidProtocolConfig
+    );
+    require!(
+        protocol.min_duration > 0 && protocol.min_duration <= protocol.max_duration,
+        LoanError::InvalidProtocolConfig
+    );
+    
+    msg!("Protocol updated by authority: {}", protocol.authority);
+    
+    Ok(())
+}
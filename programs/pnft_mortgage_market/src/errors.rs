@@ -37,4 +37,79 @@ pub enum LoanError {
     
     #[msg("Insufficient loan amount")]
     InsufficientLoanAmount,
+    
+    #[msg("Collateral is not held in the vault")]
+    CollateralNotInVault,
+    
+    #[msg("Collateral is not locked under the vault delegate")]
+    CollateralNotLocked,
+    
+    #[msg("Collateral token account not provided")]
+    MissingCollateralAccount,
+    
+    #[msg("Collateral mint must have a supply of 1 and 0 decimals")]
+    InvalidCollateralMint,
+    
+    #[msg("Collateral metadata or master edition is invalid")]
+    InvalidCollateralMetadata,
+    
+    #[msg("Collateral collection is not eligible")]
+    CollectionNotEligible,
+    
+    #[msg("Invalid collection configuration")]
+    InvalidCollectionConfig,
+    
+    #[msg("Invalid protocol configuration")]
+    InvalidProtocolConfig,
+    
+    #[msg("Signer is not an authorized price updater")]
+    UnauthorizedPriceUpdater,
+    
+    #[msg("Price update deviates too far from the TWAP")]
+    PriceDeviationTooLarge,
+    
+    #[msg("Invalid repayment amount")]
+    InvalidRepaymentAmount,
+    
+    #[msg("Invalid repayment schedule")]
+    InvalidRepaymentSchedule,
+    
+    #[msg("Loan request has expired")]
+    RequestExpired,
+    
+    #[msg("Pool does not have enough available liquidity")]
+    InsufficientLiquidity,
+    
+    #[msg("Invalid or missing lending pool")]
+    InvalidPool,
+    
+    #[msg("Math overflow")]
+    MathOverflow,
+    
+    #[msg("Division by zero")]
+    DivisionByZero,
+    
+    #[msg("Token account of the bidder being refunded is required")]
+    InvalidBidRefundAccount,
+    
+    #[msg("Auction has ended")]
+    AuctionEnded,
+    
+    #[msg("Instruction does not match the auction mode")]
+    InvalidAuctionMode,
+    
+    #[msg("Amount is too small to mint or redeem pool shares")]
+    PoolAmountTooSmall,
+    
+    #[msg("Loan is not pending")]
+    LoanNotPending,
+    
+    #[msg("Only the borrower can cancel a pending loan")]
+    UnauthorizedCancellation,
+    
+    #[msg("Loan terms differ from the expected terms")]
+    LoanTermsMismatch,
+    
+    #[msg("Pool loans cannot be extended")]
+    PoolLoanNotExtendable,
 }
@@ -11,6 +11,90 @@ pub struct LoanCreated {
     pub interest_rate: u16,
 }
 
+#[event]
+pub struct LoanCancelled {
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+    pub refunded_amount: u64,
+}
+
+#[event]
+pub struct OfferCreated {
+    pub offer: Pubkey,
+    pub lender: Pubkey,
+    pub collection: Pubkey,
+    pub loan_amount: u64,
+    pub interest_rate: u16,
+    pub duration: i64,
+    pub loan_count: u16,
+}
+
+#[event]
+pub struct OfferTaken {
+    pub offer: Pubkey,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub loans_remaining: u16,
+}
+
+#[event]
+pub struct OfferCancelled {
+    pub offer: Pubkey,
+    pub lender: Pubkey,
+    pub refunded_amount: u64,
+}
+
+#[event]
+pub struct LoanRequested {
+    pub request: Pubkey,
+    pub borrower: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub loan_mint: Pubkey,
+    pub amount: u64,
+    pub duration: i64,
+    pub interest_rate: u16,
+    pub expiry: i64,
+}
+
+#[event]
+pub struct LoanRequestFilled {
+    pub request: Pubkey,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+}
+
+#[event]
+pub struct LoanRequestCancelled {
+    pub request: Pubkey,
+    pub borrower: Pubkey,
+    pub collateral_mint: Pubkey,
+}
+
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub loan_mint: Pubkey,
+    pub share_mint: Pubkey,
+}
+
+#[event]
+pub struct PoolDeposited {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct PoolWithdrawn {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+}
+
 #[event]
 pub struct CollateralDeposited {
     pub loan: Pubkey,
@@ -18,6 +102,13 @@ pub struct CollateralDeposited {
     pub amount: u64,
 }
 
+#[event]
+pub struct CollateralLocked {
+    pub loan: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub delegate: Pubkey,
+}
+
 #[event]
 pub struct LoanRepaid {
     pub loan: Pubkey,
@@ -25,6 +116,57 @@ pub struct LoanRepaid {
     pub amount: u64,
 }
 
+#[event]
+pub struct PartialRepayment {
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub interest_paid: u64,
+    pub principal_paid: u64,
+    pub remaining_balance: u64,
+}
+
+#[event]
+pub struct InstallmentPaid {
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub installment: u16,
+    pub amount: u64,
+    pub late: bool,
+    pub remaining_balance: u64,
+    pub next_due_time: i64,
+}
+
+#[event]
+pub struct LateFeeCharged {
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub treasury_amount: u64,
+}
+
+#[event]
+pub struct LoanExtended {
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+    pub duration: i64,
+    pub interest_rate: u16,
+    pub capitalized_interest: u64,
+    pub outstanding_amount: u64,
+}
+
+#[event]
+pub struct LoanRefinanced {
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub old_lender: Pubkey,
+    pub new_lender: Pubkey,
+    pub payoff_amount: u64,
+    pub duration: i64,
+    pub interest_rate: u16,
+}
+
 #[event]
 pub struct LoanLiquidated {
     pub loan: Pubkey,
@@ -37,6 +179,7 @@ pub struct AuctionStarted {
     pub loan: Pubkey,
     pub collateral_mint: Pubkey,
     pub starting_price: u64,
+    pub reserve_price: u64,
     pub end_time: i64,
 }
 
@@ -47,9 +190,59 @@ pub struct BidPlaced {
     pub amount: u64,
 }
 
+#[event]
+pub struct AuctionExtended {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct BidRefunded {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AuctionReserveNotMet {
+    pub auction: Pubkey,
+    pub lender: Pubkey,
+    pub highest_bid: u64,
+    pub reserve_price: u64,
+}
+
+#[event]
+pub struct AuctionRelisted {
+    pub auction: Pubkey,
+    pub loan: Pubkey,
+    pub highest_bid: u64,
+    pub end_time: i64,
+}
+
 #[event]
 pub struct AuctionSettled {
     pub auction: Pubkey,
     pub winner: Pubkey,
     pub winning_bid: u64,
+    pub lender_amount: u64,
+    pub borrower_amount: u64,
+}
+
+#[event]
+pub struct FloorPriceUpdated {
+    pub feed: Pubkey,
+    pub collection_mint: Pubkey,
+    pub price: u64,
+    pub twap: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FloorPriceOutlier {
+    pub feed: Pubkey,
+    pub collection_mint: Pubkey,
+    pub price: u64,
+    pub confirmations: u8,
+    pub timestamp: i64,
 }
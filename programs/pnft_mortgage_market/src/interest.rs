@@ -0,0 +1,273 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::*;
+
+// Fixed-point scale: 1.0 == WAD
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+pub const BPS: u128 = 10_000;
+pub const SECONDS_PER_YEAR: u128 = 365 * 24 * 3600;
+
+// Terms of the Taylor series for e^x once x is reduced below 1/2
+const EXP_TAYLOR_TERMS: u128 = 24;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Compounding {
+    Simple,                             // interest on principal only
+    Periodic { periods_per_year: u32 }, // compounded n times a year
+    Continuous,                         // e^(rt)
+}
+
+// Full 256-bit product of two u128 as (high, low)
+fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+    
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+    
+    let cross = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let low = (cross << 64) | (lo_lo & MASK);
+    let high = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (cross >> 64);
+    (high, low)
+}
+
+// a * b / denominator, rounded down, with a 256-bit intermediate product
+pub fn mul_div(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    require!(denominator > 0, LoanError::DivisionByZero);
+    let (high, low) = full_mul(a, b);
+    if high == 0 {
+        return Ok(low / denominator);
+    }
+    // The quotient only fits in 128 bits when high < denominator
+    require!(high < denominator, LoanError::MathOverflow);
+    
+    let mut remainder = high;
+    let mut quotient: u128 = 0;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+    Ok(quotient)
+}
+
+pub fn wad_mul(a: u128, b: u128) -> Result<u128> {
+    mul_div(a, b, WAD)
+}
+
+// base^exp for a WAD base, by repeated squaring
+pub fn wad_pow(base: u128, mut exp: u64) -> Result<u128> {
+    let mut result = WAD;
+    let mut base = base;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = wad_mul(result, base)?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = wad_mul(base, base)?;
+        }
+    }
+    Ok(result)
+}
+
+// e^x for a WAD exponent: halve x below 1/2, sum the Taylor series, square back up
+pub fn wad_exp(x: u128) -> Result<u128> {
+    let mut reduced = x;
+    let mut squarings = 0;
+    while reduced > WAD / 2 {
+        reduced /= 2;
+        squarings += 1;
+    }
+    
+    let mut term = WAD;
+    let mut sum = WAD;
+    for n in 1..=EXP_TAYLOR_TERMS {
+        term = wad_mul(term, reduced)? / n;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+    }
+    
+    for _ in 0..squarings {
+        sum = wad_mul(sum, sum)?;
+    }
+    Ok(sum)
+}
+
+// Growth of 1.0 over `elapsed` seconds at `rate` APR bps, as a WAD factor
+pub fn growth_factor(rate: u16, elapsed: i64, compounding: Compounding) -> Result<u128> {
+    if elapsed <= 0 || rate == 0 {
+        return Ok(WAD);
+    }
+    let rate = rate as u128;
+    let elapsed = elapsed as u128;
+    
+    match compounding {
+        Compounding::Simple => {
+            let rate_time = mul_div(rate * elapsed, WAD, BPS * SECONDS_PER_YEAR)?;
+            WAD.checked_add(rate_time).ok_or(LoanError::MathOverflow.into())
+        }
+        Compounding::Periodic { periods_per_year } => {
+            require!(periods_per_year > 0, LoanError::InvalidInterestRate);
+            let periods_per_year = periods_per_year as u128;
+            let periodic_rate = rate * WAD / (BPS * periods_per_year);
+            
+            // Whole periods compound, the partial period accrues simply
+            let period_time = elapsed.checked_mul(periods_per_year).ok_or(LoanError::MathOverflow)?;
+            let whole_periods = u64::try_from(period_time / SECONDS_PER_YEAR)
+                .map_err(|_| LoanError::MathOverflow)?;
+            let partial_period = (period_time % SECONDS_PER_YEAR) * WAD / SECONDS_PER_YEAR;
+            
+            let compounded = wad_pow(WAD + periodic_rate, whole_periods)?;
+            wad_mul(compounded, WAD + wad_mul(periodic_rate, partial_period)?)
+        }
+        Compounding::Continuous => {
+            let rate_time = mul_div(rate * elapsed, WAD, BPS * SECONDS_PER_YEAR)?;
+            wad_exp(rate_time)
+        }
+    }
+}
+
+// Interest on `balance` over `elapsed` seconds, rounded down
+pub fn accrued_interest(balance: u64, rate: u16, elapsed: i64, compounding: Compounding) -> Result<u64> {
+    let growth = growth_factor(rate, elapsed, compounding)?;
+    let interest = mul_div(balance as u128, growth - WAD, WAD)?;
+    u64::try_from(interest).map_err(|_| LoanError::MathOverflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    
+    const YEAR: i64 = SECONDS_PER_YEAR as i64;
+    
+    fn to_f64(wad: u128) -> f64 {
+        wad as f64 / WAD as f64
+    }
+    
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        let error = (actual - expected).abs();
+        assert!(
+            error <= tolerance * expected.abs().max(1.0),
+            "actual {actual} expected {expected}"
+        );
+    }
+    
+    #[test]
+    fn no_interest_without_time_or_rate() {
+        assert_eq!(accrued_interest(1_000_000, 1000, 0, Compounding::Continuous).unwrap(), 0);
+        assert_eq!(accrued_interest(1_000_000, 1000, -5, Compounding::Simple).unwrap(), 0);
+        assert_eq!(accrued_interest(1_000_000, 0, YEAR, Compounding::Continuous).unwrap(), 0);
+    }
+    
+    #[test]
+    fn one_year_at_ten_percent() {
+        let principal = 1_000_000_000;
+        assert_eq!(accrued_interest(principal, 1000, YEAR, Compounding::Simple).unwrap(), 100_000_000);
+        // (1 + 0.1 / 12)^12 - 1 = 10.4713067...%
+        assert_eq!(
+            accrued_interest(principal, 1000, YEAR, Compounding::Periodic { periods_per_year: 12 }).unwrap(),
+            104_713_067
+        );
+        // e^0.1 - 1 = 10.5170918...%
+        assert_eq!(accrued_interest(principal, 1000, YEAR, Compounding::Continuous).unwrap(), 105_170_918);
+    }
+    
+    #[test]
+    fn mul_div_uses_full_width_product() {
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX).unwrap(), u128::MAX);
+        assert_eq!(mul_div(u128::MAX, 2, 4).unwrap(), u128::MAX / 2);
+        assert_eq!(mul_div(1 << 100, 1 << 100, 1 << 90).unwrap(), 1 << 110);
+        assert!(mul_div(u128::MAX, 2, 1).is_err());
+        assert!(mul_div(1, 1, 0).is_err());
+    }
+    
+    #[test]
+    fn zero_periods_per_year_is_rejected() {
+        assert!(growth_factor(1000, YEAR, Compounding::Periodic { periods_per_year: 0 }).is_err());
+    }
+    
+    #[test]
+    fn overflow_is_an_error() {
+        assert!(accrued_interest(u64::MAX, u16::MAX, 100 * YEAR, Compounding::Continuous).is_err());
+        assert!(accrued_interest(u64::MAX, u16::MAX, YEAR, Compounding::Simple).is_err());
+    }
+    
+    proptest! {
+        #[test]
+        fn simple_matches_linear_reference(
+            principal in 0u64..=1_000_000_000_000_000,
+            rate in 0u16..=u16::MAX,
+            elapsed in 0i64..=10 * YEAR,
+        ) {
+            let reference = principal as u128 * rate as u128 * elapsed as u128 / (BPS * SECONDS_PER_YEAR);
+            let interest = accrued_interest(principal, rate, elapsed, Compounding::Simple).unwrap() as u128;
+            prop_assert!(reference.abs_diff(interest) <= 1);
+        }
+        
+        #[test]
+        fn mul_div_matches_u128_when_product_fits(a in 0u128..=u64::MAX as u128, b in 0u128..=u64::MAX as u128, d in 1u128..=u128::MAX) {
+            prop_assert_eq!(mul_div(a, b, d).unwrap(), a * b / d);
+        }
+        
+        #[test]
+        fn wad_pow_matches_repeated_multiplication(base in WAD..=2 * WAD, exp in 0u64..64) {
+            let mut reference = WAD;
+            for _ in 0..exp {
+                reference = wad_mul(reference, base).unwrap();
+            }
+            assert_close(to_f64(wad_pow(base, exp).unwrap()), to_f64(reference), 1e-12);
+        }
+        
+        #[test]
+        fn wad_exp_matches_f64(x in 0u128..=20 * WAD) {
+            assert_close(to_f64(wad_exp(x).unwrap()), to_f64(x).exp(), 1e-12);
+        }
+        
+        #[test]
+        fn periodic_matches_f64_reference(
+            rate in 1u16..=20_000,
+            periods_per_year in prop::sample::select(vec![1u32, 4, 12, 52, 365]),
+            elapsed in 0i64..=5 * YEAR,
+        ) {
+            let n = periods_per_year as f64;
+            let t = elapsed as f64 / YEAR as f64;
+            let r = rate as f64 / 10_000.0;
+            let whole = (n * t).floor();
+            let reference = (1.0 + r / n).powf(whole) * (1.0 + r / n * (n * t - whole));
+            
+            let growth = growth_factor(rate, elapsed, Compounding::Periodic { periods_per_year }).unwrap();
+            assert_close(to_f64(growth), reference, 1e-9);
+        }
+        
+        #[test]
+        fn continuous_matches_f64_reference(rate in 0u16..=u16::MAX, elapsed in 0i64..=5 * YEAR) {
+            let reference = (rate as f64 / 10_000.0 * elapsed as f64 / YEAR as f64).exp();
+            let growth = growth_factor(rate, elapsed, Compounding::Continuous).unwrap();
+            assert_close(to_f64(growth), reference, 1e-12);
+        }
+        
+        #[test]
+        fn compounding_never_earns_less_than_simple(
+            principal in 0u64..=1_000_000_000_000,
+            rate in 0u16..=20_000,
+            elapsed in 0i64..=5 * YEAR,
+        ) {
+            let simple = accrued_interest(principal, rate, elapsed, Compounding::Simple).unwrap();
+            let monthly = accrued_interest(principal, rate, elapsed, Compounding::Periodic { periods_per_year: 12 }).unwrap();
+            let continuous = accrued_interest(principal, rate, elapsed, Compounding::Continuous).unwrap();
+            prop_assert!(monthly + 1 >= simple);
+            prop_assert!(continuous + 1 >= monthly);
+        }
+    }
+}
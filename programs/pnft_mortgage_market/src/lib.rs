@@ -2,141 +2,218 @@ use anchor_lang::prelude::*;
 
 declare_id!("H6UnYU1JKVzkRLf61Ew6jEU8UerFbeJ38iPyVvrjWE9Z");
 
+pub mod contexts;
+pub mod states;
+pub mod errors;
+pub mod events;
+pub mod utils;
+pub mod pnft;
+pub mod oracle;
+pub mod interest;
+
+use contexts::*;
+use states::{LoanTerms, RepaymentSchedule};
+
 #[program]
 pub mod pnft_mortgage_market {
     use super::*;
 
     // Initialize the protocol
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        let protocol = &mut ctx.accounts.protocol;
-        
-        protocol.authority = ctx.accounts.authority.key();
-        protocol.treasury = ctx.accounts.treasury.key();
-        protocol.fee_rate = 50; // 0.5% fee
-        protocol.total_loans = 0;
-        protocol.total_volume = 0;
-        protocol.bump = ctx.bumps.protocol;
-        
-        msg!("Protocol initialized with authority: {}", protocol.authority);
-        
-        Ok(())
-    }
-
-    // Create a new loan using NFT as collateral
+        contexts::initialize::handler(ctx)
+    }
+
+    // Update protocol configuration (authority only)
+    pub fn update_protocol(ctx: Context<UpdateProtocol>, params: UpdateProtocolParams) -> Result<()> {
+        contexts::update_protocol::handler(ctx, params)
+    }
+
+    // Whitelist a collection as eligible collateral (authority only)
+    pub fn create_collection_config(
+        ctx: Context<CreateCollectionConfig>,
+        params: CollectionConfigParams,
+    ) -> Result<()> {
+        contexts::collection_config::create_handler(ctx, params)
+    }
+
+    // Update collection risk parameters (authority only)
+    pub fn update_collection_config(
+        ctx: Context<UpdateCollectionConfig>,
+        params: CollectionConfigParams,
+    ) -> Result<()> {
+        contexts::collection_config::update_handler(ctx, params)
+    }
+
+    // Create the protocol-owned floor price feed of a collection (authority only)
+    pub fn create_floor_price_feed(
+        ctx: Context<CreateFloorPriceFeed>,
+        params: FloorPriceFeedParams,
+    ) -> Result<()> {
+        contexts::floor_price::create_handler(ctx, params)
+    }
+
+    // Record a floor price observation (whitelisted updaters only)
+    pub fn update_floor_price(
+        ctx: Context<UpdateFloorPrice>,
+        price: u64,
+        confidence: u64,
+        timestamp: i64,
+    ) -> Result<()> {
+        contexts::floor_price::update_handler(ctx, price, confidence, timestamp)
+    }
+
+    // Create a new loan using pNFT as collateral
     pub fn create_loan(
         ctx: Context<CreateLoan>,
         loan_amount: u64,
         duration: i64,
-        interest_rate: u16,
+        interest_rate: u16, // basis points
+        schedule: RepaymentSchedule,
     ) -> Result<()> {
-        let loan = &mut ctx.accounts.loan;
-        let clock = Clock::get()?;
-
-        // Initialize loan account
-        loan.borrower = ctx.accounts.borrower.key();
-        loan.lender = ctx.accounts.lender.key();
-        loan.collateral_mint = ctx.accounts.collateral_mint.key();
-        loan.loan_amount = loan_amount;
-        loan.outstanding_amount = loan_amount;
-        loan.interest_rate = interest_rate;
-        loan.duration = duration;
-        loan.start_time = clock.unix_timestamp;
-        loan.status = LoanStatus::Active;
-        loan.liquidation_threshold = 8000; // 80% LTV
-        loan.bump = ctx.bumps.loan;
-
-        Ok(())
+        contexts::create_loan::handler(ctx, loan_amount, duration, interest_rate, schedule)
     }
-}
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + Protocol::LEN,
-        seeds = [b"protocol"],
-        bump
-    )]
-    pub protocol: Account<'info, Protocol>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    /// CHECK: This is the treasury account for protocol fees
-    #[account(mut)]
-    pub treasury: UncheckedAccount<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+    // Create loan, vault and escrow the pNFT in a single instruction
+    pub fn open_loan(
+        ctx: Context<OpenLoan>,
+        loan_amount: u64,
+        duration: i64,
+        interest_rate: u16, // basis points
+        schedule: RepaymentSchedule,
+    ) -> Result<()> {
+        contexts::open_loan::handler(ctx, loan_amount, duration, interest_rate, schedule)
+    }
 
-#[derive(Accounts)]
-#[instruction(loan_amount: u64, duration: i64, interest_rate: u16)]
-pub struct CreateLoan<'info> {
-    #[account(mut)]
-    pub borrower: Signer<'info>,
-    
-    #[account(mut)]
-    pub lender: Signer<'info>,
-    
-    // NFT mint
-    /// CHECK: We're just storing the mint address
-    pub collateral_mint: UncheckedAccount<'info>,
-    
-    // Loan PDA
-    #[account(
-        init,
-        payer = borrower,
-        space = 8 + Loan::LEN,
-        seeds = [
-            b"loan",
-            borrower.key().as_ref(),
-            collateral_mint.key().as_ref()
-        ],
-        bump
-    )]
-    pub loan: Account<'info, Loan>,
-    
-    pub system_program: Program<'info, System>,
-}
+    // Post a lender offer, escrowing principal for one or more loans
+    pub fn create_loan_offer(ctx: Context<CreateLoanOffer>, params: LoanOfferParams) -> Result<()> {
+        contexts::offer::create_handler(ctx, params)
+    }
 
-#[account]
-pub struct Protocol {
-    pub authority: Pubkey,         // 32 bytes
-    pub treasury: Pubkey,          // 32 bytes
-    pub fee_rate: u16,             // 2 bytes - basis points
-    pub total_loans: u64,          // 8 bytes
-    pub total_volume: u64,         // 8 bytes
-    pub bump: u8,                  // 1 byte
-}
+    // Borrow against an eligible pNFT on the terms of an open offer the borrower expects
+    pub fn take_loan_offer(ctx: Context<TakeLoanOffer>, expected_terms: LoanTerms) -> Result<()> {
+        contexts::offer::take_handler(ctx, expected_terms)
+    }
 
-impl Protocol {
-    pub const LEN: usize = 32 + 32 + 2 + 8 + 8 + 1;
-}
+    // Withdraw an offer and its remaining escrowed principal
+    pub fn cancel_loan_offer(ctx: Context<CancelLoanOffer>) -> Result<()> {
+        contexts::offer::cancel_handler(ctx)
+    }
 
-#[account]
-pub struct Loan {
-    pub borrower: Pubkey,           // 32 bytes
-    pub lender: Pubkey,             // 32 bytes
-    pub collateral_mint: Pubkey,    // 32 bytes - NFT mint
-    pub loan_amount: u64,           // 8 bytes
-    pub outstanding_amount: u64,    // 8 bytes - principal + interest
-    pub interest_rate: u16,         // 2 bytes - basis points
-    pub duration: i64,              // 8 bytes - loan term in seconds
-    pub start_time: i64,            // 8 bytes - unix timestamp
-    pub status: LoanStatus,         // 1 byte
-    pub liquidation_threshold: u16, // 2 bytes - percentage
-    pub bump: u8,                   // 1 byte - PDA bump
-}
+    // Post a borrower request, escrowing the pNFT until it is filled or cancelled
+    pub fn create_loan_request(ctx: Context<CreateLoanRequest>, params: LoanRequestParams) -> Result<()> {
+        contexts::loan_request::create_handler(ctx, params)
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum LoanStatus {
-    Active,
-    Repaid,
-    Liquidated,
-    InAuction,
-}
+    // Fund an open request on the terms the lender expects and originate the loan
+    pub fn fill_loan_request(ctx: Context<FillLoanRequest>, expected_terms: LoanTerms) -> Result<()> {
+        contexts::loan_request::fill_handler(ctx, expected_terms)
+    }
+
+    // Withdraw a request and reclaim the escrowed pNFT
+    pub fn cancel_loan_request(ctx: Context<CancelLoanRequest>) -> Result<()> {
+        contexts::loan_request::cancel_handler(ctx)
+    }
+
+    // Create a lending pool for a loan mint (authority only)
+    pub fn create_lending_pool(
+        ctx: Context<CreateLendingPool>,
+        pool_id: u64,
+        params: LendingPoolParams,
+    ) -> Result<()> {
+        contexts::pool::create_handler(ctx, pool_id, params)
+    }
 
-impl Loan {
-    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 2 + 8 + 8 + 1 + 2 + 1;
-}
\ No newline at end of file
+    // Update the eligible collections and loan terms of a pool
+    pub fn update_lending_pool(ctx: Context<UpdateLendingPool>, params: LendingPoolParams) -> Result<()> {
+        contexts::pool::update_handler(ctx, params)
+    }
+
+    // Deposit liquidity into a pool for LP shares
+    pub fn deposit_to_pool(ctx: Context<PoolLiquidity>, amount: u64) -> Result<()> {
+        contexts::pool::deposit_handler(ctx, amount)
+    }
+
+    // Burn LP shares against available pool liquidity
+    pub fn withdraw_from_pool(ctx: Context<PoolLiquidity>, shares: u64) -> Result<()> {
+        contexts::pool::withdraw_handler(ctx, shares)
+    }
+
+    // Borrow from pool liquidity on the pool's terms, escrowing the pNFT
+    pub fn borrow_from_pool(ctx: Context<BorrowFromPool>, loan_amount: u64) -> Result<()> {
+        contexts::pool::borrow_handler(ctx, loan_amount)
+    }
+
+    // Create vault for loan
+    pub fn create_vault(ctx: Context<CreateVault>) -> Result<()> {
+        contexts::create_vault::handler(ctx)
+    }
+
+    // Deposit pNFT collateral into vault and activate the loan
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>) -> Result<()> {
+        contexts::deposit_collateral::handler(ctx)
+    }
+
+    // Lock pNFT collateral in the borrower's wallet and activate the loan
+    pub fn lock_collateral(ctx: Context<LockCollateral>) -> Result<()> {
+        contexts::lock_collateral::handler(ctx)
+    }
+
+    // Cancel a loan still awaiting collateral; the borrower returns the principal
+    pub fn cancel_loan(ctx: Context<CancelLoan>) -> Result<()> {
+        contexts::cancel_loan::handler(ctx)
+    }
+
+    // Repay loan and reclaim collateral
+    pub fn repay_loan(ctx: Context<RepayLoan>) -> Result<()> {
+        contexts::repay_loan::handler(ctx)
+    }
+
+    // Pay down part of the balance, interest first; collateral stays locked
+    pub fn repay_partial(ctx: Context<RepayPartial>, amount: u64) -> Result<()> {
+        contexts::repay_partial::handler(ctx, amount)
+    }
+
+    // Pay the next installment of an amortizing loan
+    pub fn pay_installment(ctx: Context<PayInstallment>) -> Result<()> {
+        contexts::pay_installment::handler(ctx)
+    }
+
+    // Extend the term with the same lender; accrued interest is capitalized or paid
+    pub fn extend_loan(
+        ctx: Context<ExtendLoan>,
+        duration: i64,
+        interest_rate: u16, // basis points
+        capitalize_interest: bool,
+    ) -> Result<()> {
+        contexts::extend_loan::handler(ctx, duration, interest_rate, capitalize_interest)
+    }
+
+    // Pay off the current lender and move the loan to a new lender and terms
+    pub fn refinance_loan(
+        ctx: Context<RefinanceLoan>,
+        duration: i64,
+        interest_rate: u16, // basis points
+    ) -> Result<()> {
+        contexts::refinance_loan::handler(ctx, duration, interest_rate)
+    }
+
+    // Liquidate undercollateralized loan
+    pub fn liquidate_loan(ctx: Context<LiquidateLoan>) -> Result<()> {
+        contexts::liquidate::handler(ctx)
+    }
+
+    // Place bid in liquidation auction
+    pub fn place_bid(ctx: Context<PlaceBid>, bid_amount: u64) -> Result<()> {
+        contexts::auction::place_bid_handler(ctx, bid_amount)
+    }
+
+    // Settle auction and transfer assets; pool loans below the reserve are relisted
+    pub fn settle_auction(ctx: Context<SettleAuction>) -> Result<()> {
+        contexts::auction::settle_handler(ctx)
+    }
+
+    // Buy Dutch auction collateral at the current price and settle immediately
+    pub fn buy_now(ctx: Context<BuyNow>) -> Result<()> {
+        contexts::auction::buy_now_handler(ctx)
+    }
+}
@@ -0,0 +1,395 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::pubkey;
+
+use crate::states::{CollectionConfig, FloorPriceFeed, OracleSource};
+use crate::errors::*;
+
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+// Switchboard on-demand mainnet and devnet programs
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM_IDS: [Pubkey; 2] = [
+    pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv"),
+    pubkey!("Aio4gaXjXzJNVLtzwtNVmSqGKpANtXhybbkhtAC94ji2"),
+];
+
+// Switchboard PullFeedAccountData is a repr(C) zero-copy account; these are
+// byte offsets into the account data, including the 8 byte discriminator.
+const SWITCHBOARD_LAST_UPDATE_OFFSET: usize = 8 + 2208;
+const SWITCHBOARD_RESULT_VALUE_OFFSET: usize = 8 + 2256;
+const SWITCHBOARD_RESULT_STD_DEV_OFFSET: usize = 8 + 2272;
+const SWITCHBOARD_ACCOUNT_LEN: usize = 8 + 3200;
+const SWITCHBOARD_PRECISION: i32 = 18;
+
+// Price read from an oracle: value = price * 10^exponent
+pub struct OraclePrice {
+    pub price: u128,
+    pub confidence: u128,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    // Reject stale or future prices and prices whose confidence interval is too wide
+    pub fn validate(&self, current_time: i64, max_price_age: u32, max_confidence_bps: u16) -> Result<()> {
+        require!(self.price > 0, LoanError::InvalidOraclePrice);
+        let age = current_time
+            .checked_sub(self.publish_time)
+            .ok_or(LoanError::InvalidOraclePrice)?;
+        require!(
+            (0..=max_price_age as i64).contains(&age),
+            LoanError::InvalidOraclePrice
+        );
+        require!(
+            self.confidence.saturating_mul(10000) <= self.price.saturating_mul(max_confidence_bps as u128),
+            LoanError::InvalidOraclePrice
+        );
+        
+        Ok(())
+    }
+    
+    // Express the price in base units of a token with `decimals` decimals
+    pub fn to_token_amount(&self, decimals: u8) -> Result<u64> {
+        let scale = (decimals as i32)
+            .checked_add(self.exponent)
+            .ok_or(LoanError::InvalidOraclePrice)?;
+        let amount = if scale >= 0 {
+            10u128.checked_pow(scale as u32).and_then(|factor| self.price.checked_mul(factor))
+        } else {
+            Some(10u128.checked_pow(scale.unsigned_abs()).map_or(0, |factor| self.price / factor))
+        };
+        
+        amount
+            .and_then(|amount| u64::try_from(amount).ok())
+            .ok_or(error!(LoanError::InvalidOraclePrice))
+    }
+}
+
+// Wire formats of the Pyth receiver accounts; not every field is used
+#[allow(dead_code)]
+#[derive(AnchorDeserialize)]
+enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+#[allow(dead_code)]
+#[derive(AnchorDeserialize)]
+struct PriceFeedMessage {
+    feed_id: [u8; 32],
+    price: i64,
+    conf: u64,
+    exponent: i32,
+    publish_time: i64,
+    prev_publish_time: i64,
+    ema_price: i64,
+    ema_conf: u64,
+}
+
+// Pyth pull-oracle `PriceUpdateV2` account
+#[allow(dead_code)]
+#[derive(AnchorDeserialize)]
+struct PriceUpdateV2 {
+    write_authority: Pubkey,
+    verification_level: VerificationLevel,
+    price_message: PriceFeedMessage,
+    posted_slot: u64,
+}
+
+fn account_discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(format!("account:{}", name).as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+fn load_pyth(config: &CollectionConfig, price_feed: &AccountInfo) -> Result<OraclePrice> {
+    require_keys_eq!(*price_feed.owner, PYTH_RECEIVER_PROGRAM_ID, LoanError::InvalidOraclePrice);
+    
+    let data = price_feed.try_borrow_data()?;
+    require!(
+        data.len() > 8 && data[..8] == account_discriminator("PriceUpdateV2"),
+        LoanError::InvalidOraclePrice
+    );
+    
+    let update = PriceUpdateV2::deserialize(&mut &data[8..])
+        .map_err(|_| error!(LoanError::InvalidOraclePrice))?;
+    
+    // Updates can be posted by anyone, so pin the feed and require full verification
+    require!(
+        matches!(update.verification_level, VerificationLevel::Full)
+            && update.price_message.feed_id == config.pyth_feed_id
+            && update.price_message.price > 0,
+        LoanError::InvalidOraclePrice
+    );
+    
+    Ok(OraclePrice {
+        price: update.price_message.price as u128,
+        confidence: update.price_message.conf as u128,
+        exponent: update.price_message.exponent,
+        publish_time: update.price_message.publish_time,
+    })
+}
+
+fn load_switchboard(config: &CollectionConfig, price_feed: &AccountInfo) -> Result<OraclePrice> {
+    require_keys_eq!(price_feed.key(), config.price_feed, LoanError::InvalidOraclePrice);
+    require!(
+        SWITCHBOARD_ON_DEMAND_PROGRAM_IDS.contains(price_feed.owner),
+        LoanError::InvalidOraclePrice
+    );
+    
+    let data = price_feed.try_borrow_data()?;
+    require!(
+        data.len() >= SWITCHBOARD_ACCOUNT_LEN
+            && data[..8] == account_discriminator("PullFeedAccountData"),
+        LoanError::InvalidOraclePrice
+    );
+    
+    let read_i128 = |offset: usize| i128::from_le_bytes(data[offset..offset + 16].try_into().unwrap());
+    let value = read_i128(SWITCHBOARD_RESULT_VALUE_OFFSET);
+    let std_dev = read_i128(SWITCHBOARD_RESULT_STD_DEV_OFFSET);
+    let last_update = i64::from_le_bytes(
+        data[SWITCHBOARD_LAST_UPDATE_OFFSET..SWITCHBOARD_LAST_UPDATE_OFFSET + 8].try_into().unwrap(),
+    );
+    
+    require!(value > 0 && std_dev >= 0, LoanError::InvalidOraclePrice);
+    
+    Ok(OraclePrice {
+        price: value as u128,
+        confidence: std_dev as u128,
+        exponent: -SWITCHBOARD_PRECISION,
+        publish_time: last_update,
+    })
+}
+
+fn load_floor_price_feed(
+    config: &CollectionConfig,
+    price_feed: &AccountInfo,
+    current_time: i64,
+) -> Result<OraclePrice> {
+    require_keys_eq!(price_feed.key(), config.price_feed, LoanError::InvalidOraclePrice);
+    require_keys_eq!(*price_feed.owner, crate::ID, LoanError::InvalidOraclePrice);
+    
+    let feed = FloorPriceFeed::try_deserialize(&mut &price_feed.try_borrow_data()?[..])?;
+    require_keys_eq!(feed.collection_mint, config.collection_mint, LoanError::InvalidOraclePrice);
+    
+    let latest = feed.latest().ok_or(LoanError::InvalidOraclePrice)?;
+    let twap = feed.twap(current_time).ok_or(LoanError::InvalidOraclePrice)?;
+    
+    Ok(OraclePrice {
+        price: twap as u128,
+        confidence: latest.confidence as u128,
+        exponent: feed.exponent,
+        publish_time: latest.timestamp,
+    })
+}
+
+pub fn load_price(
+    config: &CollectionConfig,
+    price_feed: &AccountInfo,
+    current_time: i64,
+) -> Result<OraclePrice> {
+    match config.oracle_source {
+        OracleSource::PythPull => load_pyth(config, price_feed),
+        OracleSource::Switchboard => load_switchboard(config, price_feed),
+        OracleSource::FloorPriceFeed => load_floor_price_feed(config, price_feed, current_time),
+    }
+}
+
+// Floor value of one collateral NFT in base units of the loan mint
+pub fn collateral_value(
+    config: &CollectionConfig,
+    price_feed: &AccountInfo,
+    loan_mint_decimals: u8,
+    current_time: i64,
+) -> Result<u64> {
+    let price = load_price(config, price_feed, current_time)?;
+    price.validate(current_time, config.max_price_age, config.max_confidence_bps)?;
+    price.to_token_amount(loan_mint_decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn price(price: u128, confidence: u128, exponent: i32) -> OraclePrice {
+        OraclePrice { price, confidence, exponent, publish_time: 1_000 }
+    }
+    
+    fn config(oracle_source: OracleSource, price_feed: Pubkey) -> CollectionConfig {
+        CollectionConfig {
+            collection_mint: Pubkey::default(),
+            max_ltv: 5000,
+            liquidation_threshold: 8000,
+            max_duration: 0,
+            enabled: true,
+            oracle_source,
+            price_feed,
+            pyth_feed_id: [0; 32],
+            max_price_age: 60,
+            max_confidence_bps: 200,
+            late_fee_policy: None,
+            bump: 0,
+        }
+    }
+    
+    #[test]
+    fn stale_prices_are_rejected() {
+        let price = price(100, 0, 0);
+        assert!(price.validate(1_060, 60, 100).is_ok());
+        assert!(price.validate(1_061, 60, 100).is_err());
+        assert!(self::price(0, 0, 0).validate(1_000, 60, 100).is_err());
+    }
+    
+    #[test]
+    fn future_prices_are_rejected() {
+        let price = price(100, 0, 0);
+        assert!(price.validate(1_000, 60, 100).is_ok());
+        assert!(price.validate(999, 60, 100).is_err());
+        // The age itself overflowing is an error rather than a panic
+        assert!(price.validate(i64::MIN, 60, 100).is_err());
+    }
+    
+    #[test]
+    fn confidence_is_bounded_relative_to_price() {
+        // 2% confidence interval
+        let price = price(10_000, 200, -2);
+        assert!(price.validate(1_000, 60, 200).is_ok());
+        assert!(price.validate(1_000, 60, 199).is_err());
+        assert!(price.validate(1_000, 60, 10000).is_ok());
+    }
+    
+    #[test]
+    fn exponent_is_scaled_to_mint_decimals() {
+        // 12.34 at exponent -2 in a 6 decimal mint
+        assert_eq!(price(1_234, 0, -2).to_token_amount(6).unwrap(), 12_340_000);
+        // Precision beyond the mint decimals is truncated
+        assert_eq!(price(123_456_789, 0, -8).to_token_amount(6).unwrap(), 1_234_567);
+        assert_eq!(price(5, 0, 3).to_token_amount(0).unwrap(), 5_000);
+        // A scale far below the mint decimals rounds to zero instead of failing
+        assert_eq!(price(1, 0, -60).to_token_amount(6).unwrap(), 0);
+        // Results that do not fit a u64 are errors
+        assert!(price(u64::MAX as u128, 0, 0).to_token_amount(1).is_err());
+        assert!(price(1, 0, 60).to_token_amount(0).is_err());
+        // Extreme exponents fail or round instead of overflowing the scale
+        assert!(price(1, 0, i32::MAX).to_token_amount(u8::MAX).is_err());
+        assert_eq!(price(1, 0, i32::MIN).to_token_amount(0).unwrap(), 0);
+    }
+    
+    // PriceUpdateV2 account data as posted by the Pyth receiver, built byte by byte
+    fn pyth_update(feed_id: [u8; 32], price: i64, conf: u64, exponent: i32, publish_time: i64, full: bool) -> Vec<u8> {
+        let mut data = account_discriminator("PriceUpdateV2").to_vec();
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        // VerificationLevel: Partial { num_signatures } is variant 0, Full is variant 1
+        data.extend_from_slice(if full { &[1] } else { &[0, 5] });
+        data.extend_from_slice(&feed_id);
+        data.extend_from_slice(&price.to_le_bytes());
+        data.extend_from_slice(&conf.to_le_bytes());
+        data.extend_from_slice(&exponent.to_le_bytes());
+        data.extend_from_slice(&publish_time.to_le_bytes());
+        data.extend_from_slice(&(publish_time - 1).to_le_bytes());
+        data.extend_from_slice(&price.to_le_bytes());
+        data.extend_from_slice(&conf.to_le_bytes());
+        data.extend_from_slice(&42u64.to_le_bytes());
+        data
+    }
+    
+    #[test]
+    fn pyth_price_update_is_read() {
+        let key = Pubkey::new_unique();
+        let mut config = config(OracleSource::PythPull, key);
+        config.pyth_feed_id = [7; 32];
+        // 25.5 at exponent -8 with a 0.1 confidence interval
+        let mut data = pyth_update([7; 32], 2_550_000_000, 10_000_000, -8, 1_000, true);
+        assert_eq!(data.len(), 8 + 32 + 1 + 84 + 8);
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &PYTH_RECEIVER_PROGRAM_ID, false, 0);
+        
+        let price = load_price(&config, &info, 1_000).unwrap();
+        assert_eq!(price.price, 2_550_000_000);
+        assert_eq!(price.confidence, 10_000_000);
+        assert_eq!(price.exponent, -8);
+        assert_eq!(price.publish_time, 1_000);
+        assert_eq!(collateral_value(&config, &info, 6, 1_030).unwrap(), 25_500_000);
+        // Too old for the collection's max_price_age, or published after now
+        assert!(collateral_value(&config, &info, 6, 1_061).is_err());
+        assert!(collateral_value(&config, &info, 6, 999).is_err());
+    }
+    
+    #[test]
+    fn pyth_price_update_must_be_verified_for_the_configured_feed() {
+        let key = Pubkey::new_unique();
+        let mut config = config(OracleSource::PythPull, key);
+        config.pyth_feed_id = [7; 32];
+        let load = |data: &mut Vec<u8>, owner: &Pubkey| {
+            let mut lamports = 0;
+            let info = AccountInfo::new(&key, false, false, &mut lamports, data, owner, false, 0);
+            load_price(&config, &info, 1_000).map(|price| price.price)
+        };
+        
+        assert!(load(&mut pyth_update([7; 32], 100, 0, 0, 1_000, true), &PYTH_RECEIVER_PROGRAM_ID).is_ok());
+        // Partially verified update
+        assert!(load(&mut pyth_update([7; 32], 100, 0, 0, 1_000, false), &PYTH_RECEIVER_PROGRAM_ID).is_err());
+        // Update for another feed
+        assert!(load(&mut pyth_update([8; 32], 100, 0, 0, 1_000, true), &PYTH_RECEIVER_PROGRAM_ID).is_err());
+        // Non-positive price
+        assert!(load(&mut pyth_update([7; 32], -100, 0, 0, 1_000, true), &PYTH_RECEIVER_PROGRAM_ID).is_err());
+        // Account not owned by the Pyth receiver
+        assert!(load(&mut pyth_update([7; 32], 100, 0, 0, 1_000, true), &Pubkey::new_unique()).is_err());
+        // Wrong discriminator and truncated data
+        let mut data = pyth_update([7; 32], 100, 0, 0, 1_000, true);
+        data[0] ^= 1;
+        assert!(load(&mut data, &PYTH_RECEIVER_PROGRAM_ID).is_err());
+        let mut data = pyth_update([7; 32], 100, 0, 0, 1_000, true);
+        data.truncate(60);
+        assert!(load(&mut data, &PYTH_RECEIVER_PROGRAM_ID).is_err());
+    }
+    
+    #[test]
+    fn switchboard_feed_is_read_at_its_byte_offsets() {
+        let key = Pubkey::new_unique();
+        let owner = SWITCHBOARD_ON_DEMAND_PROGRAM_IDS[0];
+        let mut data = vec![0u8; SWITCHBOARD_ACCOUNT_LEN];
+        data[..8].copy_from_slice(&account_discriminator("PullFeedAccountData"));
+        data[SWITCHBOARD_LAST_UPDATE_OFFSET..SWITCHBOARD_LAST_UPDATE_OFFSET + 8]
+            .copy_from_slice(&1_000i64.to_le_bytes());
+        // 25.5 at 18 decimals with a 0.1 standard deviation
+        data[SWITCHBOARD_RESULT_VALUE_OFFSET..SWITCHBOARD_RESULT_VALUE_OFFSET + 16]
+            .copy_from_slice(&25_500_000_000_000_000_000i128.to_le_bytes());
+        data[SWITCHBOARD_RESULT_STD_DEV_OFFSET..SWITCHBOARD_RESULT_STD_DEV_OFFSET + 16]
+            .copy_from_slice(&100_000_000_000_000_000i128.to_le_bytes());
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        
+        let config = config(OracleSource::Switchboard, key);
+        let price = load_price(&config, &info, 1_000).unwrap();
+        assert_eq!(price.exponent, -18);
+        assert_eq!(price.publish_time, 1_000);
+        assert_eq!(price.confidence, 100_000_000_000_000_000);
+        assert_eq!(price.to_token_amount(6).unwrap(), 25_500_000);
+        assert_eq!(collateral_value(&config, &info, 6, 1_030).unwrap(), 25_500_000);
+        // Too old for the collection's max_price_age
+        assert!(collateral_value(&config, &info, 6, 1_061).is_err());
+    }
+    
+    #[test]
+    fn switchboard_feed_must_match_config_and_owner() {
+        let key = Pubkey::new_unique();
+        let owner = SWITCHBOARD_ON_DEMAND_PROGRAM_IDS[1];
+        let mut data = vec![0u8; SWITCHBOARD_ACCOUNT_LEN];
+        data[..8].copy_from_slice(&account_discriminator("PullFeedAccountData"));
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        
+        // Zero result value
+        assert!(load_price(&config(OracleSource::Switchboard, key), &info, 0).is_err());
+        // Feed other than the configured one
+        let other = config(OracleSource::Switchboard, Pubkey::new_unique());
+        assert!(load_price(&other, &info, 0).is_err());
+        
+        let wrong_owner = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; 8];
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &wrong_owner, false, 0);
+        assert!(load_price(&config(OracleSource::Switchboard, key), &info, 0).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Mint, Token};
+use mpl_token_metadata::accounts::{MasterEdition, Metadata};
+use mpl_token_metadata::types::TokenStandard;
+use mpl_token_metadata::instructions::{
+    DelegateLockedTransferV1CpiBuilder, LockV1CpiBuilder, RevokeLockedTransferV1CpiBuilder,
+    TransferV1CpiBuilder, UnlockV1CpiBuilder,
+};
+
+use crate::states::{CollectionConfig, Protocol};
+use crate::errors::*;
+
+// Check that the collateral is a 1-of-1 NFT with a master edition and a token
+// standard the protocol accepts, returning its Metaplex metadata.
+pub fn validate_collateral(
+    protocol: &Protocol,
+    mint: &Account<Mint>,
+    metadata: &AccountInfo,
+    edition: &AccountInfo,
+) -> Result<Metadata> {
+    require!(mint.supply == 1 && mint.decimals == 0, LoanError::InvalidCollateralMint);
+    
+    require_keys_eq!(*metadata.owner, mpl_token_metadata::ID, LoanError::InvalidCollateralMetadata);
+    require_keys_eq!(*edition.owner, mpl_token_metadata::ID, LoanError::InvalidCollateralMetadata);
+    
+    let metadata = Metadata::safe_deserialize(&metadata.try_borrow_data()?)
+        .map_err(|_| LoanError::InvalidCollateralMetadata)?;
+    require_keys_eq!(metadata.mint, mint.key(), LoanError::InvalidCollateralMetadata);
+    
+    MasterEdition::safe_deserialize(&edition.try_borrow_data()?)
+        .map_err(|_| LoanError::InvalidCollateralMetadata)?;
+    
+    // Metadata created before token standards existed has none set
+    let token_standard = metadata.token_standard.unwrap_or(TokenStandard::NonFungible);
+    require!(protocol.is_token_standard_allowed(token_standard), LoanError::NotProgrammableNFT);
+    
+    Ok(metadata)
+}
+
+// Check that the collateral belongs to the verified collection of an
+// enabled collection config.
+pub fn validate_collection(metadata: &Metadata, collection_config: &CollectionConfig) -> Result<()> {
+    let collection = metadata.collection.as_ref().ok_or(LoanError::CollectionNotEligible)?;
+    
+    require!(
+        collection.verified
+            && collection.key == collection_config.collection_mint
+            && collection_config.enabled,
+        LoanError::CollectionNotEligible
+    );
+    
+    Ok(())
+}
+
+// Token Metadata accounts every CPI on the collateral pNFT needs, shared by all
+// instructions that move or lock collateral. The instruction checks them
+// against its collateral mint with `is_collateral`.
+#[derive(Accounts)]
+pub struct PnftAccounts<'info> {
+    /// CHECK: Metadata PDA of the collateral mint, checked by is_collateral
+    #[account(mut)]
+    pub collateral_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Master edition PDA of the collateral mint, checked by is_collateral
+    pub collateral_edition: UncheckedAccount<'info>,
+
+    /// CHECK: Rule set from the collateral's programmable config, validated by Token Metadata
+    pub authorization_rules: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Token Auth Rules program, validated by Token Metadata
+    pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Token Metadata program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+}
+
+// One side of a pNFT transfer or lock: the token account, its owner and the
+// token record of programmable NFTs
+pub struct PnftHolder<'info> {
+    pub token: AccountInfo<'info>,
+    pub owner: AccountInfo<'info>,
+    pub token_record: Option<AccountInfo<'info>>,
+}
+
+impl<'info> PnftHolder<'info> {
+    pub fn new(
+        token: AccountInfo<'info>,
+        owner: AccountInfo<'info>,
+        token_record: &Option<UncheckedAccount<'info>>,
+    ) -> Self {
+        Self {
+            token,
+            owner,
+            token_record: token_record.as_ref().map(|a| a.to_account_info()),
+        }
+    }
+}
+
+impl<'info> PnftAccounts<'info> {
+    // Metadata and master edition are the Token Metadata PDAs of `mint`
+    pub fn is_collateral(&self, mint: &Pubkey) -> bool {
+        self.collateral_metadata.key() == Metadata::find_pda(mint).0
+            && self.collateral_edition.key() == MasterEdition::find_pda(mint).0
+    }
+
+    // Move the pNFT from `source` to `destination`, signed by `authority`
+    #[allow(clippy::too_many_arguments)]
+    pub fn transfer(
+        &self,
+        mint: &Account<'info, Mint>,
+        source: PnftHolder<'info>,
+        destination: PnftHolder<'info>,
+        authority: AccountInfo<'info>,
+        payer: AccountInfo<'info>,
+        system_program: &Program<'info, System>,
+        token_program: &Program<'info, Token>,
+        associated_token_program: &Program<'info, AssociatedToken>,
+    ) -> PnftTransfer<'info> {
+        PnftTransfer {
+            token_metadata_program: self.token_metadata_program.to_account_info(),
+            token: source.token,
+            token_owner: source.owner,
+            destination_token: destination.token,
+            destination_owner: destination.owner,
+            mint: mint.to_account_info(),
+            metadata: self.collateral_metadata.to_account_info(),
+            edition: self.collateral_edition.to_account_info(),
+            token_record: source.token_record,
+            destination_token_record: destination.token_record,
+            authority,
+            payer,
+            system_program: system_program.to_account_info(),
+            sysvar_instructions: self.sysvar_instructions.to_account_info(),
+            spl_token_program: token_program.to_account_info(),
+            spl_ata_program: associated_token_program.to_account_info(),
+            authorization_rules_program: self.authorization_rules_program.as_ref().map(|a| a.to_account_info()),
+            authorization_rules: self.authorization_rules.as_ref().map(|a| a.to_account_info()),
+        }
+    }
+
+    // Lock or unlock the pNFT of `holder` under `delegate`
+    pub fn lock(
+        &self,
+        mint: &Account<'info, Mint>,
+        holder: PnftHolder<'info>,
+        delegate: AccountInfo<'info>,
+        payer: AccountInfo<'info>,
+        system_program: &Program<'info, System>,
+        token_program: &Program<'info, Token>,
+    ) -> PnftLock<'info> {
+        PnftLock {
+            token_metadata_program: self.token_metadata_program.to_account_info(),
+            token: holder.token,
+            token_owner: holder.owner,
+            delegate,
+            mint: mint.to_account_info(),
+            metadata: self.collateral_metadata.to_account_info(),
+            edition: self.collateral_edition.to_account_info(),
+            token_record: holder.token_record,
+            payer,
+            system_program: system_program.to_account_info(),
+            sysvar_instructions: self.sysvar_instructions.to_account_info(),
+            spl_token_program: token_program.to_account_info(),
+            authorization_rules_program: self.authorization_rules_program.as_ref().map(|a| a.to_account_info()),
+            authorization_rules: self.authorization_rules.as_ref().map(|a| a.to_account_info()),
+        }
+    }
+}
+
+// Accounts required to move a pNFT through Token Metadata `TransferV1`.
+// Programmable NFTs keep their token accounts frozen, so a plain SPL
+// transfer fails; Token Metadata thaws, transfers and re-freezes while
+// enforcing the collection's rule set.
+pub struct PnftTransfer<'info> {
+    pub token_metadata_program: AccountInfo<'info>,
+    pub token: AccountInfo<'info>,
+    pub token_owner: AccountInfo<'info>,
+    pub destination_token: AccountInfo<'info>,
+    pub destination_owner: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub metadata: AccountInfo<'info>,
+    pub edition: AccountInfo<'info>,
+    pub token_record: Option<AccountInfo<'info>>,
+    pub destination_token_record: Option<AccountInfo<'info>>,
+    pub authority: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub spl_token_program: AccountInfo<'info>,
+    pub spl_ata_program: AccountInfo<'info>,
+    pub authorization_rules_program: Option<AccountInfo<'info>>,
+    pub authorization_rules: Option<AccountInfo<'info>>,
+}
+
+impl<'info> PnftTransfer<'info> {
+    pub fn invoke(&self) -> Result<()> {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        TransferV1CpiBuilder::new(&self.token_metadata_program)
+            .token(&self.token)
+            .token_owner(&self.token_owner)
+            .destination_token(&self.destination_token)
+            .destination_owner(&self.destination_owner)
+            .mint(&self.mint)
+            .metadata(&self.metadata)
+            .edition(Some(&self.edition))
+            .token_record(self.token_record.as_ref())
+            .destination_token_record(self.destination_token_record.as_ref())
+            .authority(&self.authority)
+            .payer(&self.payer)
+            .system_program(&self.system_program)
+            .sysvar_instructions(&self.sysvar_instructions)
+            .spl_token_program(&self.spl_token_program)
+            .spl_ata_program(&self.spl_ata_program)
+            .authorization_rules_program(self.authorization_rules_program.as_ref())
+            .authorization_rules(self.authorization_rules.as_ref())
+            .amount(1) // NFTs have amount 1
+            .invoke_signed(signer_seeds)?;
+
+        Ok(())
+    }
+}
+
+// Accounts required to lock a pNFT in its owner's wallet under a program
+// delegate. The delegate is a LockedTransfer delegate whose only allowed
+// destination is the delegate itself: it can lock and unlock the token and,
+// on default, pull it into escrow, but cannot send it anywhere else.
+pub struct PnftLock<'info> {
+    pub token_metadata_program: AccountInfo<'info>,
+    pub token: AccountInfo<'info>,
+    pub token_owner: AccountInfo<'info>,
+    pub delegate: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub metadata: AccountInfo<'info>,
+    pub edition: AccountInfo<'info>,
+    pub token_record: Option<AccountInfo<'info>>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub spl_token_program: AccountInfo<'info>,
+    pub authorization_rules_program: Option<AccountInfo<'info>>,
+    pub authorization_rules: Option<AccountInfo<'info>>,
+}
+
+impl<'info> PnftLock<'info> {
+    // Approve the delegate and lock the token. The token owner must sign.
+    pub fn delegate_and_lock(&self, delegate_seeds: &[&[&[u8]]]) -> Result<()> {
+        DelegateLockedTransferV1CpiBuilder::new(&self.token_metadata_program)
+            .delegate(&self.delegate)
+            .metadata(&self.metadata)
+            .master_edition(Some(&self.edition))
+            .token_record(self.token_record.as_ref())
+            .mint(&self.mint)
+            .token(&self.token)
+            .authority(&self.token_owner)
+            .payer(&self.payer)
+            .system_program(&self.system_program)
+            .sysvar_instructions(&self.sysvar_instructions)
+            .spl_token_program(Some(&self.spl_token_program))
+            .authorization_rules_program(self.authorization_rules_program.as_ref())
+            .authorization_rules(self.authorization_rules.as_ref())
+            .amount(1)
+            .locked_address(self.delegate.key())
+            .invoke()?;
+
+        LockV1CpiBuilder::new(&self.token_metadata_program)
+            .authority(&self.delegate)
+            .token_owner(Some(&self.token_owner))
+            .token(&self.token)
+            .mint(&self.mint)
+            .metadata(&self.metadata)
+            .edition(Some(&self.edition))
+            .token_record(self.token_record.as_ref())
+            .payer(&self.payer)
+            .system_program(&self.system_program)
+            .sysvar_instructions(&self.sysvar_instructions)
+            .spl_token_program(Some(&self.spl_token_program))
+            .authorization_rules_program(self.authorization_rules_program.as_ref())
+            .authorization_rules(self.authorization_rules.as_ref())
+            .invoke_signed(delegate_seeds)?;
+
+        Ok(())
+    }
+
+    pub fn unlock(&self, delegate_seeds: &[&[&[u8]]]) -> Result<()> {
+        UnlockV1CpiBuilder::new(&self.token_metadata_program)
+            .authority(&self.delegate)
+            .token_owner(Some(&self.token_owner))
+            .token(&self.token)
+            .mint(&self.mint)
+            .metadata(&self.metadata)
+            .edition(Some(&self.edition))
+            .token_record(self.token_record.as_ref())
+            .payer(&self.payer)
+            .system_program(&self.system_program)
+            .sysvar_instructions(&self.sysvar_instructions)
+            .spl_token_program(Some(&self.spl_token_program))
+            .authorization_rules_program(self.authorization_rules_program.as_ref())
+            .authorization_rules(self.authorization_rules.as_ref())
+            .invoke_signed(delegate_seeds)?;
+
+        Ok(())
+    }
+
+    // Remove the delegate once the token is unlocked. The token owner must sign.
+    pub fn revoke(&self) -> Result<()> {
+        RevokeLockedTransferV1CpiBuilder::new(&self.token_metadata_program)
+            .delegate(&self.delegate)
+            .metadata(&self.metadata)
+            .master_edition(Some(&self.edition))
+            .token_record(self.token_record.as_ref())
+            .mint(&self.mint)
+            .token(&self.token)
+            .authority(&self.token_owner)
+            .payer(&self.payer)
+            .system_program(&self.system_program)
+            .sysvar_instructions(&self.sysvar_instructions)
+            .spl_token_program(Some(&self.spl_token_program))
+            .authorization_rules_program(self.authorization_rules_program.as_ref())
+            .authorization_rules(self.authorization_rules.as_ref())
+            .invoke()?;
+
+        Ok(())
+    }
+}
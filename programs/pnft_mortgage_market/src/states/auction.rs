@@ -1,14 +1,29 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::*;
+use crate::interest::{mul_div, wad_exp, WAD};
+use crate::utils::{checked_add, checked_mul_div};
+
+// ln 2 in WAD fixed-point
+const LN_2: u128 = 693_147_180_559_945_309;
+
 #[account]
 pub struct Auction {
     pub loan: Pubkey,              // 32 bytes
     pub collateral_mint: Pubkey,   // 32 bytes
-    pub starting_price: u64,       // 8 bytes
+    pub starting_price: u64,       // 8 bytes - minimum first bid
     pub current_bid: u64,          // 8 bytes
     pub current_bidder: Pubkey,    // 32 bytes
     pub end_time: i64,             // 8 bytes
     pub status: AuctionStatus,     // 1 byte
+    pub min_bid_increment_bps: u16, // 2 bytes - over the current bid
+    pub reserve_price: u64,        // 8 bytes - below it the collateral goes to the lender
+    pub extension_window: i64,     // 8 bytes - bids this close to end_time extend it
+    pub extension_duration: i64,   // 8 bytes - seconds added per late bid
+    pub max_end_time: i64,         // 8 bytes - end_time cap across all extensions
+    pub mode: AuctionMode,         // 12 bytes
+    pub start_time: i64,           // 8 bytes - unix timestamp
+    pub floor_price: u64,          // 8 bytes - lowest Dutch auction price
     pub bump: u8,                  // 1 byte
 }
 
@@ -19,10 +34,304 @@ pub enum AuctionStatus {
     Cancelled,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuctionMode {
+    English,                                            // ascending bids until end_time
+    Dutch { curve: PriceCurve, floor_price_bps: u16 },  // descending price, first buyer wins
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PriceCurve {
+    Linear,                         // straight line from starting price to floor at end_time
+    Exponential { half_life: i64 }, // distance to the floor halves every half_life seconds
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct AuctionConfig {
+    pub duration: i64,             // 8 bytes - seconds
+    pub starting_price_bps: u16,   // 2 bytes - of the outstanding debt
+    pub min_bid_increment_bps: u16, // 2 bytes
+    pub reserve_price_bps: u16,    // 2 bytes - of the outstanding debt
+    pub extension_window: i64,     // 8 bytes - seconds before the end that extend it
+    pub extension_duration: i64,   // 8 bytes - seconds added per late bid
+    pub max_extension: i64,        // 8 bytes - total seconds the end can move
+    pub mode: AuctionMode,         // 12 bytes
+}
+
+impl AuctionConfig {
+    pub const LEN: usize = 8 + 2 + 2 + 2 + 8 + 8 + 8 + AuctionMode::LEN;
+    
+    pub fn is_valid(&self) -> bool {
+        let valid_mode = match self.mode {
+            AuctionMode::English => true,
+            // Every price on the curve must clear the reserve
+            AuctionMode::Dutch { curve, floor_price_bps } => {
+                floor_price_bps <= self.starting_price_bps
+                    && floor_price_bps >= self.reserve_price_bps
+                    && !matches!(curve, PriceCurve::Exponential { half_life } if half_life <= 0)
+            }
+        };
+        self.duration > 0
+            && self.extension_window >= 0
+            && self.extension_duration >= 0
+            && self.max_extension >= 0
+            && valid_mode
+    }
+}
+
+impl AuctionMode {
+    pub const LEN: usize = 1 + (1 + 8) + 2;
+}
+
 impl Auction {
-    pub const LEN: usize = 32 + 32 + 8 + 8 + 32 + 8 + 1 + 1;
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 32 + 8 + 1 + 2 + 8 + 8 + 8 + 8
+        + AuctionMode::LEN + 8 + 8 + 1;
     
     pub fn is_active(&self, current_time: i64) -> bool {
         self.status == AuctionStatus::Active && current_time < self.end_time
     }
+    
+    // Lowest acceptable bid: the starting price, then the current bid plus the increment
+    pub fn min_bid(&self) -> Result<u64> {
+        if self.current_bid == 0 {
+            return Ok(self.starting_price.max(1));
+        }
+        let increment = checked_mul_div(self.current_bid, self.min_bid_increment_bps as u64, 10000)?;
+        checked_add(self.current_bid, increment.max(1))
+    }
+    
+    // A sale needs a bid at or above the reserve price
+    pub fn meets_reserve(&self) -> bool {
+        self.current_bid > 0 && self.current_bid >= self.reserve_price
+    }
+    
+    // Push end_time out for a bid inside the extension window, up to max_end_time.
+    // Returns whether the auction was extended.
+    pub fn extend_for_bid(&mut self, current_time: i64) -> Result<bool> {
+        let window_start = self.end_time.saturating_sub(self.extension_window);
+        if self.extension_duration == 0 || current_time < window_start {
+            return Ok(false);
+        }
+        let extended = self.end_time
+            .checked_add(self.extension_duration)
+            .ok_or(LoanError::MathOverflow)?
+            .min(self.max_end_time);
+        if extended <= self.end_time {
+            return Ok(false);
+        }
+        self.end_time = extended;
+        Ok(true)
+    }
+    
+    // Dutch auction price at `current_time`, decaying from starting_price towards
+    // floor_price at end_time. There is no price from end_time on: the auction can
+    // only be settled, like an English auction without bids.
+    pub fn current_price(&self, current_time: i64) -> Result<u64> {
+        let AuctionMode::Dutch { curve, .. } = self.mode else {
+            return err!(LoanError::InvalidAuctionMode);
+        };
+        require!(current_time < self.end_time, LoanError::AuctionEnded);
+        let duration = self.end_time.saturating_sub(self.start_time).max(1);
+        let elapsed = current_time.saturating_sub(self.start_time).clamp(0, duration);
+        let range = self.starting_price.saturating_sub(self.floor_price);
+        
+        let remaining = match curve {
+            PriceCurve::Linear => {
+                range - checked_mul_div(range, elapsed as u64, duration as u64)?
+            }
+            PriceCurve::Exponential { half_life } => {
+                require!(half_life > 0, LoanError::InvalidAuctionMode);
+                let halvings = elapsed / half_life;
+                if halvings >= 64 {
+                    0
+                } else {
+                    // 2^-f for the fractional half-life, as 1 / e^(f ln 2)
+                    let fraction = mul_div((elapsed % half_life) as u128, LN_2, half_life as u128)?;
+                    let decay = mul_div(WAD, WAD, wad_exp(fraction)?)?;
+                    mul_div((range >> halvings) as u128, decay, WAD)? as u64
+                }
+            }
+        };
+        checked_add(self.floor_price, remaining)
+    }
+    
+    // Restart an auction that ended below its reserve from `current_time`, without
+    // a reserve so the next round can clear at any bid
+    pub fn relist(&mut self, current_time: i64, duration: i64, max_extension: i64) -> Result<()> {
+        self.current_bid = 0;
+        self.current_bidder = Pubkey::default();
+        self.reserve_price = 0;
+        self.start_time = current_time;
+        self.end_time = current_time
+            .checked_add(duration)
+            .ok_or(LoanError::MathOverflow)?;
+        self.max_end_time = self.end_time
+            .checked_add(max_extension)
+            .ok_or(LoanError::MathOverflow)?;
+        Ok(())
+    }
+    
+    // Owner of the collateral once the auction settles
+    pub fn collateral_recipient(&self, lender: Pubkey) -> Pubkey {
+        if self.meets_reserve() {
+            self.current_bidder
+        } else {
+            lender
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn auction(current_bid: u64) -> Auction {
+        Auction {
+            loan: Pubkey::default(),
+            collateral_mint: Pubkey::default(),
+            starting_price: 500,
+            current_bid,
+            current_bidder: Pubkey::new_unique(),
+            end_time: 100,
+            status: AuctionStatus::Active,
+            min_bid_increment_bps: 100,
+            reserve_price: 800,
+            extension_window: 10,
+            extension_duration: 15,
+            max_end_time: 130,
+            mode: AuctionMode::English,
+            start_time: 0,
+            floor_price: 0,
+            bump: 0,
+        }
+    }
+    
+    #[test]
+    fn first_bid_opens_at_starting_price() {
+        assert_eq!(auction(0).min_bid().unwrap(), 500);
+        let mut free = auction(0);
+        free.starting_price = 0;
+        assert_eq!(free.min_bid().unwrap(), 1);
+    }
+    
+    #[test]
+    fn later_bids_clear_the_increment() {
+        assert_eq!(auction(1_000).min_bid().unwrap(), 1_010);
+        // Increments round down but always move the price
+        assert_eq!(auction(50).min_bid().unwrap(), 51);
+        assert!(auction(u64::MAX).min_bid().is_err());
+    }
+    
+    #[test]
+    fn collateral_goes_to_lender_below_reserve() {
+        let lender = Pubkey::new_unique();
+        assert_eq!(auction(0).collateral_recipient(lender), lender);
+        assert_eq!(auction(799).collateral_recipient(lender), lender);
+        let sold = auction(800);
+        assert_eq!(sold.collateral_recipient(lender), sold.current_bidder);
+    }
+    
+    #[test]
+    fn late_bids_extend_up_to_the_cap() {
+        let mut auction = auction(0);
+        assert!(!auction.extend_for_bid(89).unwrap());
+        assert_eq!(auction.end_time, 100);
+        
+        assert!(auction.extend_for_bid(90).unwrap());
+        assert_eq!(auction.end_time, 115);
+        assert!(auction.extend_for_bid(110).unwrap());
+        assert_eq!(auction.end_time, 130);
+        assert!(!auction.extend_for_bid(125).unwrap());
+        assert_eq!(auction.end_time, 130);
+    }
+    
+    #[test]
+    fn extensions_can_be_disabled() {
+        let mut auction = auction(0);
+        auction.extension_duration = 0;
+        assert!(!auction.extend_for_bid(99).unwrap());
+        auction.extension_duration = 15;
+        auction.max_end_time = auction.end_time;
+        assert!(!auction.extend_for_bid(99).unwrap());
+        assert_eq!(auction.end_time, 100);
+    }
+    
+    fn dutch(curve: PriceCurve) -> Auction {
+        let mut auction = auction(0);
+        auction.mode = AuctionMode::Dutch { curve, floor_price_bps: 5000 };
+        auction.starting_price = 1_500_000;
+        auction.floor_price = 500_000;
+        auction
+    }
+    
+    #[test]
+    fn linear_price_falls_to_the_floor() {
+        let auction = dutch(PriceCurve::Linear);
+        assert_eq!(auction.current_price(-10).unwrap(), 1_500_000);
+        assert_eq!(auction.current_price(0).unwrap(), 1_500_000);
+        assert_eq!(auction.current_price(25).unwrap(), 1_250_000);
+        assert_eq!(auction.current_price(50).unwrap(), 1_000_000);
+        assert_eq!(auction.current_price(99).unwrap(), 510_000);
+    }
+    
+    #[test]
+    fn dutch_auction_has_no_price_after_the_end() {
+        for curve in [PriceCurve::Linear, PriceCurve::Exponential { half_life: 20 }] {
+            let auction = dutch(curve);
+            assert!(auction.is_active(99));
+            assert!(auction.current_price(99).unwrap() >= auction.floor_price);
+            assert!(!auction.is_active(100));
+            assert!(auction.current_price(100).is_err());
+            assert!(auction.current_price(1_000).is_err());
+        }
+    }
+    
+    #[test]
+    fn dutch_floor_must_clear_the_reserve() {
+        let mut config = AuctionConfig {
+            duration: 100,
+            starting_price_bps: 10000,
+            min_bid_increment_bps: 0,
+            reserve_price_bps: 6000,
+            extension_window: 0,
+            extension_duration: 0,
+            max_extension: 0,
+            mode: AuctionMode::Dutch { curve: PriceCurve::Linear, floor_price_bps: 5000 },
+        };
+        assert!(!config.is_valid());
+        config.reserve_price_bps = 5000;
+        assert!(config.is_valid());
+    }
+    
+    #[test]
+    fn exponential_price_halves_the_distance_to_the_floor() {
+        let auction = dutch(PriceCurve::Exponential { half_life: 20 });
+        assert_eq!(auction.current_price(0).unwrap(), 1_500_000);
+        assert_eq!(auction.current_price(20).unwrap(), 1_000_000);
+        assert_eq!(auction.current_price(40).unwrap(), 750_000);
+        // 2^-0.5 of the way between the first two halvings
+        assert_eq!(auction.current_price(10).unwrap(), 1_207_106);
+        assert_eq!(auction.current_price(80).unwrap(), 562_500);
+    }
+    
+    #[test]
+    fn english_auctions_have_no_current_price() {
+        assert!(auction(0).current_price(0).is_err());
+    }
+    
+    #[test]
+    fn relisting_drops_the_reserve_and_restarts_the_clock() {
+        let mut auction = auction(600);
+        assert!(!auction.meets_reserve());
+        auction.relist(200, 100, 30).unwrap();
+        assert_eq!(auction.current_bid, 0);
+        assert_eq!(auction.reserve_price, 0);
+        assert_eq!((auction.start_time, auction.end_time, auction.max_end_time), (200, 300, 330));
+        assert!(auction.is_active(250));
+        // Any bid at the starting price now clears
+        auction.current_bid = auction.min_bid().unwrap();
+        assert!(auction.meets_reserve());
+        assert!(auction.relist(i64::MAX, 1, 0).is_err());
+    }
 }
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::states::LateFeePolicy;
+
+#[account]
+pub struct CollectionConfig {
+    pub collection_mint: Pubkey,    // 32 bytes - verified Metaplex collection
+    pub max_ltv: u16,               // 2 bytes - basis points
+    pub liquidation_threshold: u16, // 2 bytes - basis points
+    pub max_duration: i64,          // 8 bytes - seconds
+    pub enabled: bool,              // 1 byte
+    pub oracle_source: OracleSource, // 1 byte - floor price feed format
+    pub price_feed: Pubkey,         // 32 bytes - Switchboard or floor price feed account
+    pub pyth_feed_id: [u8; 32],     // 32 bytes - Pyth price feed id
+    pub max_price_age: u32,         // 4 bytes - seconds
+    pub max_confidence_bps: u16,    // 2 bytes - max confidence interval / price
+    pub late_fee_policy: Option<LateFeePolicy>, // 1 + 14 bytes - overrides the protocol default
+    pub bump: u8,                   // 1 byte
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum OracleSource {
+    PythPull,     // Pyth receiver PriceUpdateV2
+    Switchboard,  // Switchboard on-demand PullFeedAccountData
+    FloorPriceFeed, // protocol-owned keeper feed, valued at its TWAP
+}
+
+impl CollectionConfig {
+    pub const LEN: usize = 32 + 2 + 2 + 8 + 1 + 1 + 32 + 32 + 4 + 2 + (1 + LateFeePolicy::LEN) + 1;
+}
@@ -0,0 +1,287 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PriceObservation {
+    pub price: u64,                // 8 bytes - price * 10^exponent
+    pub confidence: u64,           // 8 bytes - same units as price
+    pub timestamp: i64,            // 8 bytes - unix timestamp
+}
+
+impl PriceObservation {
+    pub const LEN: usize = 8 + 8 + 8;
+}
+
+#[account]
+pub struct FloorPriceFeed {
+    pub collection_mint: Pubkey,   // 32 bytes
+    pub exponent: i32,             // 4 bytes
+    pub max_deviation_bps: u16,    // 2 bytes - max distance of an update from the TWAP
+    pub twap_window: i64,          // 8 bytes - seconds
+    pub observations: [PriceObservation; 16], // 16 * 24 bytes - ring buffer
+    pub head: u8,                  // 1 byte - next slot to write
+    pub count: u8,                 // 1 byte - number of filled slots
+    pub confirmations_required: u8, // 1 byte - consistent outliers that confirm a market move
+    pub candidate: PriceObservation, // 24 bytes - latest outlier awaiting confirmation
+    pub candidate_confirmations: u8, // 1 byte - 0 when no outlier is pending
+    pub candidate_updaters: [Pubkey; 8], // 8 * 32 bytes - updaters that confirmed the candidate
+    pub bump: u8,                  // 1 byte
+}
+
+impl FloorPriceFeed {
+    pub const CAPACITY: usize = 16;
+    pub const MAX_CONFIRMATIONS: usize = 8;
+    pub const LEN: usize = 32 + 4 + 2 + 8 + PriceObservation::LEN * Self::CAPACITY + 1 + 1
+        + 1 + PriceObservation::LEN + 1 + 32 * Self::MAX_CONFIRMATIONS + 1;
+    
+    pub fn latest(&self) -> Option<&PriceObservation> {
+        if self.count == 0 {
+            return None;
+        }
+        let index = (self.head as usize + Self::CAPACITY - 1) % Self::CAPACITY;
+        Some(&self.observations[index])
+    }
+    
+    pub fn push(&mut self, observation: PriceObservation) {
+        self.observations[self.head as usize] = observation;
+        self.head = ((self.head as usize + 1) % Self::CAPACITY) as u8;
+        self.count = (self.count as usize + 1).min(Self::CAPACITY) as u8;
+    }
+    
+    // Record an observation, returning whether it entered the ring buffer. An
+    // update too far from the TWAP is held as a candidate; once
+    // `confirmations_required` consecutive updates agree with it, the market has
+    // moved and the buffer restarts from the confirmed price instead of
+    // rejecting every later update against the stale average. Each updater
+    // confirms a candidate at most once, so no keeper can confirm its own outlier.
+    pub fn record(&mut self, observation: PriceObservation, updater: Pubkey, current_time: i64) -> bool {
+        let within_twap = match self.twap(current_time) {
+            Some(twap) => self.within_deviation(observation.price, twap),
+            None => true,
+        };
+        if within_twap {
+            self.candidate_confirmations = 0;
+            self.push(observation);
+            return true;
+        }
+        
+        if self.candidate_confirmations == 0
+            || !self.within_deviation(observation.price, self.candidate.price)
+        {
+            self.candidate_confirmations = 0;
+        }
+        let confirmations = self.candidate_confirmations as usize;
+        if !self.candidate_updaters[..confirmations].contains(&updater) {
+            self.candidate_updaters[confirmations] = updater;
+            self.candidate_confirmations += 1;
+        }
+        
+        if self.candidate_confirmations < self.confirmations_required {
+            self.candidate = observation;
+            return false;
+        }
+        
+        // Confirmed: drop the pre-move history and keep the agreeing updates
+        let candidate = self.candidate;
+        self.observations = [PriceObservation::default(); Self::CAPACITY];
+        self.head = 0;
+        self.count = 0;
+        self.candidate_confirmations = 0;
+        self.push(candidate);
+        self.push(observation);
+        true
+    }
+    
+    // Latest timestamp recorded, including a pending candidate
+    pub fn last_timestamp(&self) -> Option<i64> {
+        let latest = self.latest().map(|observation| observation.timestamp);
+        if self.candidate_confirmations > 0 {
+            return latest.max(Some(self.candidate.timestamp));
+        }
+        latest
+    }
+    
+    fn within_deviation(&self, price: u64, reference: u64) -> bool {
+        let deviation = (price as u128).abs_diff(reference as u128) * 10000 / reference as u128;
+        deviation <= self.max_deviation_bps as u128
+    }
+    
+    // Time-weighted average of the observations inside the window ending at
+    // `current_time`. Each observation is weighted by how long it was the
+    // latest price; the newest one counts until `current_time`.
+    pub fn twap(&self, current_time: i64) -> Option<u64> {
+        let window_start = current_time - self.twap_window;
+        let oldest = (self.head as usize + Self::CAPACITY - self.count as usize) % Self::CAPACITY;
+        
+        let mut weighted_sum: u128 = 0;
+        let mut total_weight: u128 = 0;
+        let mut last_price: Option<u64> = None;
+        
+        for i in 0..self.count as usize {
+            let observation = &self.observations[(oldest + i) % Self::CAPACITY];
+            let until = if i + 1 < self.count as usize {
+                self.observations[(oldest + i + 1) % Self::CAPACITY].timestamp
+            } else {
+                current_time
+            };
+            let from = observation.timestamp.max(window_start);
+            
+            if until > from {
+                let weight = (until - from) as u128;
+                weighted_sum += observation.price as u128 * weight;
+                total_weight += weight;
+            }
+            if observation.timestamp >= window_start {
+                last_price = Some(observation.price);
+            }
+        }
+        
+        if total_weight == 0 {
+            // Only an update posted at `current_time` falls in the window
+            return last_price;
+        }
+        Some((weighted_sum / total_weight) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn feed(twap_window: i64) -> FloorPriceFeed {
+        FloorPriceFeed {
+            collection_mint: Pubkey::default(),
+            exponent: 0,
+            max_deviation_bps: 1_000,
+            twap_window,
+            observations: [PriceObservation::default(); FloorPriceFeed::CAPACITY],
+            head: 0,
+            count: 0,
+            confirmations_required: 3,
+            candidate: PriceObservation::default(),
+            candidate_confirmations: 0,
+            candidate_updaters: [Pubkey::default(); FloorPriceFeed::MAX_CONFIRMATIONS],
+            bump: 0,
+        }
+    }
+    
+    fn observation(price: u64, timestamp: i64) -> PriceObservation {
+        PriceObservation { price, confidence: 0, timestamp }
+    }
+    
+    fn keeper(n: u8) -> Pubkey {
+        Pubkey::new_from_array([n; 32])
+    }
+    
+    #[test]
+    fn empty_feed_has_no_price() {
+        let feed = feed(100);
+        assert!(feed.latest().is_none());
+        assert_eq!(feed.twap(1_000), None);
+        assert_eq!(feed.last_timestamp(), None);
+    }
+    
+    #[test]
+    fn ring_buffer_wraps_around() {
+        let mut feed = feed(100);
+        for i in 0..20 {
+            feed.push(observation(100 + i, i as i64));
+        }
+        assert_eq!(feed.count as usize, FloorPriceFeed::CAPACITY);
+        assert_eq!(feed.head, 4);
+        assert_eq!(feed.latest().unwrap().price, 119);
+        // The four oldest observations were overwritten
+        assert!(feed.observations.iter().all(|o| o.price >= 104));
+    }
+    
+    #[test]
+    fn twap_weights_by_time_within_the_window() {
+        let mut feed = feed(100);
+        feed.push(observation(100, 0));
+        feed.push(observation(200, 50));
+        // 100 for 50s, 200 for 50s
+        assert_eq!(feed.twap(100), Some(150));
+        // Window [50, 150]: only the 200 observation counts
+        assert_eq!(feed.twap(150), Some(200));
+        // Window [25, 125]: 100 for 25s, 200 for 75s
+        assert_eq!(feed.twap(125), Some(175));
+    }
+    
+    #[test]
+    fn twap_window_edges() {
+        let mut feed = feed(100);
+        feed.push(observation(100, 0));
+        // The only observation is older than the window but is still the latest price
+        assert_eq!(feed.twap(500), Some(100));
+        // An update posted at current_time has no weight yet but is returned on its own
+        let mut feed = self::feed(100);
+        feed.push(observation(300, 1_000));
+        assert_eq!(feed.twap(1_000), Some(300));
+        // A newer observation at current_time does not shift the average
+        let mut feed = self::feed(100);
+        feed.push(observation(100, 900));
+        feed.push(observation(300, 1_000));
+        assert_eq!(feed.twap(1_000), Some(100));
+    }
+    
+    #[test]
+    fn twap_after_wraparound() {
+        let mut feed = feed(1_000);
+        for i in 0..20 {
+            feed.push(observation(if i < 18 { 100 } else { 200 }, i * 10));
+        }
+        // Window [0, 200] covers the 16 retained observations from t=40;
+        // 100 until t=180, then 200 for 20s
+        assert_eq!(feed.twap(200), Some((100 * 140 + 200 * 20) / 160));
+    }
+    
+    #[test]
+    fn single_outlier_is_held_back() {
+        let mut feed = feed(100);
+        assert!(feed.record(observation(100, 0), keeper(1), 0));
+        assert!(!feed.record(observation(200, 10), keeper(1), 10));
+        assert_eq!(feed.latest().unwrap().price, 100);
+        assert_eq!(feed.last_timestamp(), Some(10));
+        // A price back in range clears the candidate
+        assert!(feed.record(observation(105, 20), keeper(1), 20));
+        assert_eq!(feed.candidate_confirmations, 0);
+    }
+    
+    #[test]
+    fn confirmed_move_resets_the_twap() {
+        let mut feed = feed(100);
+        assert!(feed.record(observation(100, 0), keeper(1), 0));
+        assert!(!feed.record(observation(200, 10), keeper(1), 10));
+        assert!(!feed.record(observation(205, 20), keeper(2), 20));
+        assert!(feed.record(observation(210, 30), keeper(3), 30));
+        // History now starts at the last candidate before confirmation
+        assert_eq!(feed.count, 2);
+        assert_eq!(feed.twap(30), Some(205));
+        // Later updates at the new level are accepted directly
+        assert!(feed.record(observation(212, 40), keeper(1), 40));
+    }
+    
+    #[test]
+    fn one_updater_cannot_confirm_its_own_outlier() {
+        let mut feed = feed(100);
+        assert!(feed.record(observation(100, 0), keeper(1), 0));
+        for t in 1..10 {
+            assert!(!feed.record(observation(200, t * 10), keeper(2), t * 10));
+        }
+        assert_eq!(feed.candidate_confirmations, 1);
+        // A second updater adds one confirmation, a third one confirms the move
+        assert!(!feed.record(observation(200, 100), keeper(3), 100));
+        assert!(!feed.record(observation(200, 110), keeper(2), 110));
+        assert!(feed.record(observation(200, 120), keeper(1), 120));
+        assert_eq!(feed.latest().unwrap().price, 200);
+    }
+    
+    #[test]
+    fn inconsistent_outliers_do_not_confirm() {
+        let mut feed = feed(100);
+        assert!(feed.record(observation(100, 0), keeper(1), 0));
+        assert!(!feed.record(observation(200, 10), keeper(1), 10));
+        assert!(!feed.record(observation(20, 20), keeper(1), 20));
+        assert!(!feed.record(observation(200, 30), keeper(1), 30));
+        assert_eq!(feed.latest().unwrap().price, 100);
+    }
+}
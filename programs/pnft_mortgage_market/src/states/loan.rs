@@ -1,17 +1,36 @@
 use anchor_lang::prelude::*;
 
+use crate::states::LateFeePolicy;
+use crate::errors::*;
+use crate::interest::{accrued_interest, Compounding};
+use crate::utils::{calculate_health_ratio, checked_add};
+
 #[account]
 pub struct Loan {
     pub borrower: Pubkey,           // 32 bytes
     pub lender: Pubkey,             // 32 bytes  
     pub collateral_mint: Pubkey,    // 32 bytes - pNFT mint
+    pub collection: Pubkey,         // 32 bytes - verified collection of the pNFT
+    pub loan_mint: Pubkey,          // 32 bytes - SPL mint the principal is paid in
     pub loan_amount: u64,           // 8 bytes
-    pub outstanding_amount: u64,    // 8 bytes - principal + interest
+    pub outstanding_amount: u64,    // 8 bytes - principal + accrued interest
+    pub accrued_interest: u64,      // 8 bytes - unpaid interest included in outstanding_amount
+    pub last_accrual_time: i64,     // 8 bytes - unix timestamp interest is accrued to
     pub interest_rate: u16,         // 2 bytes - basis points
     pub duration: i64,              // 8 bytes - loan term in seconds
     pub start_time: i64,            // 8 bytes - unix timestamp
     pub status: LoanStatus,         // 1 byte
     pub liquidation_threshold: u16,  // 2 bytes - percentage
+    pub collateral_mode: CollateralMode, // 1 byte
+    pub schedule: RepaymentSchedule, // 9 bytes
+    pub installment_amount: u64,    // 8 bytes - level payment per period, 0 for bullet loans
+    pub installment_count: u16,     // 2 bytes
+    pub installments_paid: u16,     // 2 bytes
+    pub late_payments: u16,         // 2 bytes - installments paid after their due time
+    pub next_due_time: i64,         // 8 bytes - unix timestamp of the next payment
+    pub late_fee_policy: LateFeePolicy, // 14 bytes - snapshot at origination
+    pub pool_funded: bool,          // 1 byte - lender is a LendingPool
+    pub compounding: Compounding,   // 5 bytes - snapshot at origination
     pub bump: u8,                   // 1 byte - PDA bump
 }
 
@@ -21,23 +40,282 @@ pub enum LoanStatus {
     Repaid,  
     Liquidated,
     InAuction,
+    Pending,  // awaiting collateral in the vault, appended to keep existing discriminants
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CollateralMode {
+    Escrow,        // pNFT held in the vault token account
+    DelegateLock,  // pNFT locked in the borrower's wallet, vault is delegate
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RepaymentSchedule {
+    Bullet,                         // principal + interest due at maturity
+    Amortizing { period: i64 },     // level installments every `period` seconds
+}
+
+impl RepaymentSchedule {
+    // Number of installments over `duration`; amortizing loans need a whole number of periods
+    pub fn installment_count(&self, duration: i64) -> Result<u16> {
+        match *self {
+            RepaymentSchedule::Bullet => Ok(1),
+            RepaymentSchedule::Amortizing { period } => {
+                require!(
+                    period > 0 && duration % period == 0,
+                    LoanError::InvalidRepaymentSchedule
+                );
+                Ok(u16::try_from(duration / period).map_err(|_| LoanError::InvalidRepaymentSchedule)?)
+            }
+        }
+    }
+}
+
+// Terms a counterparty expects when acting on a standing offer or request
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct LoanTerms {
+    pub loan_amount: u64,
+    pub interest_rate: u16,         // basis points
+    pub duration: i64,              // seconds
+    pub schedule: RepaymentSchedule,
 }
 
 impl Loan {
-    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 2 + 8 + 8 + 1 + 2 + 1;
+    pub const LEN: usize = 32 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 2 + 8 + 8 + 1 + 2 + 1
+        + 9 + 8 + 2 + 2 + 2 + 8 + LateFeePolicy::LEN + 1 + 5 + 1;
+    
+    // Expired and delinquent loans are liquidatable without a price; the collateral
+    // is only valued, through `collateral_value`, for the undercollateralization check
+    pub fn is_liquidatable(
+        &self,
+        current_time: i64,
+        collateral_value: impl FnOnce() -> Result<u64>,
+    ) -> Result<bool> {
+        // Overdue payments only count once the grace period has run out
+        let liquidation_time = self.maturity()
+            .checked_add(self.late_fee_policy.grace_period)
+            .ok_or(LoanError::MathOverflow)?;
+        if current_time > liquidation_time || self.is_installment_overdue(current_time) {
+            return Ok(true);
+        }
+        
+        // A loan with nothing outstanding cannot be undercollateralized
+        if self.outstanding_amount == 0 {
+            return Ok(false);
+        }
+        Ok(calculate_health_ratio(collateral_value()?, self.outstanding_amount)?
+            < self.liquidation_threshold as u64)
+    }
+    
+    pub fn is_installment_overdue(&self, current_time: i64) -> bool {
+        matches!(self.schedule, RepaymentSchedule::Amortizing { .. })
+            && current_time > self.next_due_time.saturating_add(self.late_fee_policy.grace_period)
+    }
+    
+    // Balance past due at `current_time`: everything once the loan has matured,
+    // otherwise one installment per due time that has passed
+    pub fn overdue_amount(&self, current_time: i64) -> u64 {
+        if current_time <= self.next_due_time {
+            return 0;
+        }
+        match self.schedule {
+            RepaymentSchedule::Amortizing { period } if current_time <= self.maturity() && period > 0 => {
+                let missed = current_time.abs_diff(self.next_due_time).div_ceil(period as u64);
+                self.installment_amount
+                    .saturating_mul(missed)
+                    .min(self.outstanding_amount)
+            }
+            _ => self.outstanding_amount,
+        }
+    }
+    
+    pub fn maturity(&self) -> i64 {
+        self.start_time + self.duration
+    }
+    
+    pub fn outstanding_principal(&self) -> u64 {
+        self.outstanding_amount.saturating_sub(self.accrued_interest)
+    }
+    
+    // Interest since the last accrual: simple interest accrues on the principal,
+    // compounding interest on the whole balance
+    pub fn calculate_interest(&self, current_time: i64) -> Result<u64> {
+        let balance = match self.compounding {
+            Compounding::Simple => self.outstanding_principal(),
+            _ => self.outstanding_amount,
+        };
+        accrued_interest(
+            balance,
+            self.interest_rate,
+            current_time.saturating_sub(self.last_accrual_time),
+            self.compounding,
+        )
+    }
+    
+    pub fn accrue_interest(&mut self, current_time: i64) -> Result<()> {
+        let interest = self.calculate_interest(current_time)?;
+        self.accrued_interest = checked_add(self.accrued_interest, interest)?;
+        self.outstanding_amount = checked_add(self.outstanding_amount, interest)?;
+        self.last_accrual_time = self.last_accrual_time.max(current_time);
+        Ok(())
+    }
+    
+    // Apply a payment to accrued interest first, then principal.
+    // Returns (interest_paid, principal_paid).
+    pub fn apply_payment(&mut self, amount: u64) -> (u64, u64) {
+        let interest_paid = amount.min(self.accrued_interest);
+        let principal_paid = (amount - interest_paid).min(self.outstanding_principal());
+        self.accrued_interest -= interest_paid;
+        self.outstanding_amount -= interest_paid + principal_paid;
+        (interest_paid, principal_paid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::calculate_installment;
+    
+    const DAY: i64 = 24 * 3600;
+    
+    fn loan(principal: u64, interest_rate: u16) -> Loan {
+        Loan {
+            borrower: Pubkey::default(),
+            lender: Pubkey::default(),
+            collateral_mint: Pubkey::default(),
+            collection: Pubkey::default(),
+            loan_mint: Pubkey::default(),
+            loan_amount: principal,
+            outstanding_amount: principal,
+            accrued_interest: 0,
+            last_accrual_time: 0,
+            interest_rate,
+            duration: 0,
+            start_time: 0,
+            status: LoanStatus::Active,
+            liquidation_threshold: 0,
+            collateral_mode: CollateralMode::Escrow,
+            schedule: RepaymentSchedule::Bullet,
+            installment_amount: 0,
+            installment_count: 0,
+            installments_paid: 0,
+            late_payments: 0,
+            next_due_time: 0,
+            late_fee_policy: LateFeePolicy {
+                grace_period: 0,
+                flat_fee_bps: 0,
+                daily_fee_bps: 0,
+                treasury_share_bps: 0,
+            },
+            pool_funded: false,
+            compounding: Compounding::Simple,
+            bump: 0,
+        }
+    }
+    
+    // Pay every installment on its due date; the last one settles whatever is left
+    fn amortize(principal: u64, interest_rate: u16, period: i64, count: u16) -> (u64, u64) {
+        let mut loan = loan(principal, interest_rate);
+        let installment = calculate_installment(principal, interest_rate, period, count).unwrap();
+        for i in 1..count as i64 {
+            loan.accrue_interest(i * period).unwrap();
+            let (interest_paid, principal_paid) = loan.apply_payment(installment);
+            assert_eq!(interest_paid + principal_paid, installment);
+        }
+        loan.accrue_interest(count as i64 * period).unwrap();
+        (installment, loan.outstanding_amount)
+    }
+    
+    #[test]
+    fn last_installment_clears_the_balance() {
+        for (principal, interest_rate, period, count) in [
+            (1_000_000_000, 1200, 30 * DAY, 12),
+            (5_000_000, 500, 7 * DAY, 52),
+            (1_000, 0, DAY, 3),
+            (123_456_789, 2500, DAY, 90),
+        ] {
+            let (installment, final_balance) = amortize(principal, interest_rate, period, count);
+            // The installment is rounded up and interest accrues rounded down, each by
+            // under one unit per period, so the final payment is never a balloon and
+            // falls short of a full installment by less than two units per period
+            assert!(final_balance <= installment, "final {final_balance} installment {installment}");
+            assert!(
+                installment - final_balance < 2 * count as u64,
+                "final {final_balance} installment {installment}"
+            );
+        }
+    }
+    
+    #[test]
+    fn payments_go_to_interest_first() {
+        let mut loan = loan(1_000, 1000);
+        loan.accrued_interest = 50;
+        loan.outstanding_amount = 1_050;
+        assert_eq!(loan.apply_payment(30), (30, 0));
+        assert_eq!(loan.apply_payment(100), (20, 80));
+        assert_eq!(loan.outstanding_amount, 920);
+        // Overpayment is capped at the balance
+        assert_eq!(loan.apply_payment(5_000), (0, 920));
+        assert_eq!(loan.outstanding_amount, 0);
+    }
+    
+    #[test]
+    fn overdue_loans_are_liquidatable_without_a_price() {
+        let mut loan = loan(1_000, 1000);
+        loan.duration = 30 * DAY;
+        loan.late_fee_policy.grace_period = 3 * DAY;
+        loan.liquidation_threshold = 11000;
+        let no_price = || err!(LoanError::InvalidOraclePrice);
+        
+        // Past maturity plus grace the oracle is never consulted
+        assert!(loan.is_liquidatable(33 * DAY + 1, no_price).unwrap());
+        // Inside the term only the undercollateralization check can trigger
+        assert!(loan.is_liquidatable(33 * DAY, no_price).is_err());
+        assert!(!loan.is_liquidatable(33 * DAY, || Ok(1_100)).unwrap());
+        assert!(loan.is_liquidatable(33 * DAY, || Ok(1_099)).unwrap());
+    }
+    
+    #[test]
+    fn accrued_interest_can_make_a_loan_undercollateralized() {
+        let mut loan = loan(1_000_000, 3650);
+        loan.duration = 365 * DAY;
+        loan.liquidation_threshold = 11000;
+        let collateral = || Ok(1_150_000);
+        assert!(!loan.is_liquidatable(200 * DAY, collateral).unwrap());
+        
+        // 36.5% APR for 200 days adds 200_000 of interest, past the threshold
+        loan.accrue_interest(200 * DAY).unwrap();
+        assert!(loan.is_liquidatable(200 * DAY, collateral).unwrap());
+    }
     
-    pub fn is_liquidatable(&self, current_time: i64, collateral_value: u64) -> bool {
-        // Check if loan has expired or is undercollateralized
-        let is_expired = current_time > self.start_time + self.duration;
-        let collateral_ratio = (collateral_value * 10000) / self.outstanding_amount;
-        let is_undercollateralized = collateral_ratio < self.liquidation_threshold as u64;
+    #[test]
+    fn only_missed_installments_are_overdue() {
+        let mut loan = loan(1_000, 0);
+        loan.duration = 90 * DAY;
+        loan.schedule = RepaymentSchedule::Amortizing { period: 30 * DAY };
+        loan.installment_amount = 334;
+        loan.next_due_time = 30 * DAY;
+        assert_eq!(loan.overdue_amount(30 * DAY), 0);
+        assert_eq!(loan.overdue_amount(30 * DAY + 1), 334);
+        assert_eq!(loan.overdue_amount(60 * DAY + 1), 668);
+        // Past maturity the whole balance is due
+        assert_eq!(loan.overdue_amount(90 * DAY + 1), 1_000);
         
-        is_expired || is_undercollateralized
+        // A bullet loan is due in full at maturity
+        loan.schedule = RepaymentSchedule::Bullet;
+        loan.next_due_time = loan.maturity();
+        assert_eq!(loan.overdue_amount(90 * DAY), 0);
+        assert_eq!(loan.overdue_amount(90 * DAY + 1), 1_000);
     }
     
-    pub fn calculate_interest(&self, current_time: i64) -> u64 {
-        let elapsed = current_time - self.start_time;
-        let annual_rate = self.interest_rate as u64;
-        (self.loan_amount * annual_rate * elapsed as u64) / (10000 * 365 * 24 * 3600)
+    #[test]
+    fn amortizing_schedules_need_whole_periods() {
+        assert_eq!(RepaymentSchedule::Bullet.installment_count(30 * DAY).unwrap(), 1);
+        let monthly = RepaymentSchedule::Amortizing { period: 30 * DAY };
+        assert_eq!(monthly.installment_count(360 * DAY).unwrap(), 12);
+        assert!(monthly.installment_count(365 * DAY).is_err());
+        assert!(RepaymentSchedule::Amortizing { period: 0 }.installment_count(30 * DAY).is_err());
+        // More installments than the count can hold
+        assert!(RepaymentSchedule::Amortizing { period: 1 }.installment_count(70_000).is_err());
     }
 }
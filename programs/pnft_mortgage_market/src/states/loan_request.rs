@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::states::{LoanTerms, RepaymentSchedule};
+
+#[account]
+pub struct LoanRequest {
+    pub borrower: Pubkey,           // 32 bytes
+    pub collateral_mint: Pubkey,    // 32 bytes - pNFT mint escrowed by the request
+    pub collection: Pubkey,         // 32 bytes - verified collection of the pNFT
+    pub loan_mint: Pubkey,          // 32 bytes - SPL mint the principal is paid in
+    pub loan_amount: u64,           // 8 bytes
+    pub interest_rate: u16,         // 2 bytes - basis points
+    pub duration: i64,              // 8 bytes - loan term in seconds
+    pub schedule: RepaymentSchedule, // 9 bytes
+    pub expiry: i64,                // 8 bytes - unix timestamp after which it cannot be filled
+    pub bump: u8,                   // 1 byte - PDA bump
+}
+
+impl LoanRequest {
+    pub const LEN: usize = 32 + 32 + 32 + 32 + 8 + 2 + 8 + 9 + 8 + 1;
+    
+    pub fn terms(&self) -> LoanTerms {
+        LoanTerms {
+            loan_amount: self.loan_amount,
+            interest_rate: self.interest_rate,
+            duration: self.duration,
+            schedule: self.schedule,
+        }
+    }
+}
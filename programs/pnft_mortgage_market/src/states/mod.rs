@@ -2,8 +2,18 @@ pub mod loan;
 pub mod vault;
 pub mod auction;
 pub mod protocol;
+pub mod collection;
+pub mod floor_price;
+pub mod offer;
+pub mod loan_request;
+pub mod pool;
 
 pub use loan::*;
 pub use vault::*;
 pub use auction::*;
 pub use protocol::*;
+pub use collection::*;
+pub use floor_price::*;
+pub use offer::*;
+pub use loan_request::*;
+pub use pool::*;
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::states::{LoanTerms, RepaymentSchedule};
+
+#[account]
+pub struct LoanOffer {
+    pub lender: Pubkey,             // 32 bytes
+    pub collection: Pubkey,         // 32 bytes - collection the offer lends against
+    pub loan_mint: Pubkey,          // 32 bytes - SPL mint the principal is paid in
+    pub loan_amount: u64,           // 8 bytes - principal per loan
+    pub interest_rate: u16,         // 2 bytes - basis points
+    pub duration: i64,              // 8 bytes - loan term in seconds
+    pub schedule: RepaymentSchedule, // 9 bytes
+    pub loans_remaining: u16,       // 2 bytes - loans still fundable from escrow
+    pub nonce: u64,                 // 8 bytes - lets a lender keep several offers open
+    pub bump: u8,                   // 1 byte - PDA bump
+}
+
+impl LoanOffer {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 2 + 8 + 9 + 2 + 8 + 1;
+    
+    pub fn terms(&self) -> LoanTerms {
+        LoanTerms {
+            loan_amount: self.loan_amount,
+            interest_rate: self.interest_rate,
+            duration: self.duration,
+            schedule: self.schedule,
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::*;
+use crate::states::RepaymentSchedule;
+use crate::interest::mul_div;
+use crate::utils::{calculate_borrow_rate, calculate_utilization, checked_add};
+
+#[account]
+pub struct LendingPool {
+    pub authority: Pubkey,          // 32 bytes - manages the pool terms
+    pub loan_mint: Pubkey,          // 32 bytes - SPL mint deposited and lent
+    pub share_mint: Pubkey,         // 32 bytes - LP share mint, authority is the pool
+    pub pool_id: u64,               // 8 bytes - distinguishes pools of the same loan mint
+    pub collections: Vec<Pubkey>,   // 4 + 32 * MAX_COLLECTIONS bytes - eligible collections
+    pub rate_model: InterestRateModel, // 8 bytes - utilization based APR
+    pub duration: i64,              // 8 bytes - loan term in seconds
+    pub max_loan_amount: u64,       // 8 bytes - principal cap per loan
+    pub schedule: RepaymentSchedule, // 9 bytes
+    pub total_borrowed: u64,        // 8 bytes - principal currently lent out
+    pub bump: u8,                   // 1 byte
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct InterestRateModel {
+    pub base_rate: u16,             // 2 bytes - APR bps at 0% utilization
+    pub slope1: u16,                // 2 bytes - APR bps added from 0% to the kink
+    pub kink: u16,                  // 2 bytes - utilization bps where slope2 takes over
+    pub slope2: u16,                // 2 bytes - APR bps added from the kink to 100%
+}
+
+impl InterestRateModel {
+    pub const LEN: usize = 2 + 2 + 2 + 2;
+    
+    pub fn is_valid(&self) -> bool {
+        self.kink <= 10000
+    }
+    
+    pub fn borrow_rate(&self, utilization: u64) -> u16 {
+        let rate = calculate_borrow_rate(utilization, self.base_rate, self.slope1, self.kink, self.slope2);
+        rate.min(u16::MAX as u64) as u16
+    }
+}
+
+impl LendingPool {
+    pub const MAX_COLLECTIONS: usize = 8;
+    // Virtual shares and assets priced into every deposit and withdrawal. They keep the
+    // first deposit at 1:1 but make inflating the share price with a direct transfer
+    // cost the attacker ~VIRTUAL_SHARES times what later depositors lose to rounding.
+    pub const VIRTUAL_SHARES: u64 = 1_000;
+    pub const VIRTUAL_ASSETS: u64 = 1_000;
+    pub const LEN: usize = 32 + 32 + 32 + 8 + (4 + 32 * Self::MAX_COLLECTIONS) + InterestRateModel::LEN
+        + 8 + 8 + 9 + 8 + 1;
+    
+    // Idle liquidity plus principal out on loan
+    pub fn total_assets(&self, available_liquidity: u64) -> Result<u64> {
+        checked_add(available_liquidity, self.total_borrowed)
+    }
+    
+    // Shares minted for a deposit, rounded down in favour of the pool
+    pub fn shares_for_deposit(&self, amount: u64, total_assets: u64, total_shares: u64) -> Result<u64> {
+        let shares = mul_div(
+            amount as u128,
+            total_shares as u128 + Self::VIRTUAL_SHARES as u128,
+            total_assets as u128 + Self::VIRTUAL_ASSETS as u128,
+        )?;
+        u64::try_from(shares).map_err(|_| LoanError::MathOverflow.into())
+    }
+    
+    // Tokens paid out for burning shares, rounded down in favour of the pool
+    pub fn amount_for_shares(&self, shares: u64, total_assets: u64, total_shares: u64) -> Result<u64> {
+        let amount = mul_div(
+            shares as u128,
+            total_assets as u128 + Self::VIRTUAL_ASSETS as u128,
+            total_shares as u128 + Self::VIRTUAL_SHARES as u128,
+        )?;
+        u64::try_from(amount).map_err(|_| LoanError::MathOverflow.into())
+    }
+    
+    // APR in bps for a loan originated at the current utilization
+    pub fn current_borrow_rate(&self, available_liquidity: u64) -> Result<u16> {
+        let utilization = calculate_utilization(self.total_borrowed, self.total_assets(available_liquidity)?);
+        Ok(self.rate_model.borrow_rate(utilization))
+    }
+    
+    // Program-derived token account holding the pool's idle liquidity
+    pub fn liquidity_address(pool: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"pool_liquidity", pool.as_ref()], &crate::ID).0
+    }
+    
+    pub fn accepts_collection(&self, collection: &Pubkey) -> bool {
+        self.collections.contains(collection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn pool(total_borrowed: u64) -> LendingPool {
+        LendingPool {
+            authority: Pubkey::default(),
+            loan_mint: Pubkey::default(),
+            share_mint: Pubkey::default(),
+            pool_id: 0,
+            collections: vec![],
+            rate_model: InterestRateModel { base_rate: 0, slope1: 0, kink: 0, slope2: 0 },
+            duration: 0,
+            max_loan_amount: 0,
+            schedule: RepaymentSchedule::Bullet,
+            total_borrowed,
+            bump: 0,
+        }
+    }
+    
+    #[test]
+    fn total_assets_counts_principal_on_loan() {
+        assert_eq!(pool(400).total_assets(600).unwrap(), 1_000);
+        assert!(pool(u64::MAX).total_assets(1).is_err());
+    }
+    
+    #[test]
+    fn first_deposit_mints_one_to_one() {
+        let pool = pool(0);
+        assert_eq!(pool.shares_for_deposit(1_000_000, 0, 0).unwrap(), 1_000_000);
+        assert_eq!(pool.amount_for_shares(1_000_000, 1_000_000, 1_000_000).unwrap(), 1_000_000);
+    }
+    
+    #[test]
+    fn rounding_favours_the_pool() {
+        let pool = pool(0);
+        // Share price ~1.5: 10 tokens buy 6 shares, which redeem for 8 tokens
+        let shares = pool.shares_for_deposit(10, 1_500_000, 1_000_000).unwrap();
+        assert_eq!(shares, 6);
+        assert_eq!(pool.amount_for_shares(shares, 1_500_000, 1_000_000).unwrap(), 8);
+        // Depositing and immediately withdrawing never returns more than was paid in
+        for amount in [1, 7, 999, 1_000_001] {
+            let shares = pool.shares_for_deposit(amount, 3_333_333, 2_000_000).unwrap();
+            let redeemed = pool.amount_for_shares(
+                shares,
+                3_333_333 + amount,
+                2_000_000 + shares,
+            ).unwrap();
+            assert!(redeemed <= amount);
+        }
+    }
+    
+    #[test]
+    fn donation_does_not_steal_from_the_next_depositor() {
+        let pool = pool(0);
+        // Attacker mints 1 share, then sends 1M tokens straight to the liquidity account
+        let attacker_shares = pool.shares_for_deposit(1, 0, 0).unwrap();
+        assert_eq!(attacker_shares, 1);
+        let assets = 1 + 1_000_000;
+        
+        // Victim deposits 500k and still receives shares
+        let victim_shares = pool.shares_for_deposit(500_000, assets, attacker_shares).unwrap();
+        assert!(victim_shares > 0);
+        let assets = assets + 500_000;
+        let supply = attacker_shares + victim_shares;
+        
+        // The attacker cannot get back more than they put in
+        let attacker_out = pool.amount_for_shares(attacker_shares, assets, supply).unwrap();
+        assert!(attacker_out < 1 + 1_000_000);
+        // And the victim loses at most a rounding share of the donation
+        let victim_out = pool.amount_for_shares(victim_shares, assets - attacker_out, supply - attacker_shares).unwrap();
+        assert!(victim_out + 1_000 >= 500_000, "victim redeemed {victim_out}");
+    }
+}
@@ -1,4 +1,10 @@
 use anchor_lang::prelude::*;
+use mpl_token_metadata::types::TokenStandard;
+
+use crate::errors::*;
+use crate::states::AuctionConfig;
+use crate::interest::Compounding;
+use crate::utils::checked_mul_div;
 
 #[account]
 pub struct Protocol {
@@ -7,9 +13,135 @@ pub struct Protocol {
     pub fee_rate: u16,             // 2 bytes - basis points
     pub total_loans: u64,          // 8 bytes
     pub total_volume: u64,         // 8 bytes
+    pub allowed_token_standards: u8, // 1 byte - bitmask of accepted TokenStandard values
+    pub price_updaters: Vec<Pubkey>, // 4 + 32 * MAX_PRICE_UPDATERS bytes - floor price keepers
+    pub min_interest_rate: u16,    // 2 bytes - APR basis points
+    pub max_interest_rate: u16,    // 2 bytes - APR basis points
+    pub min_duration: i64,         // 8 bytes - seconds
+    pub max_duration: i64,         // 8 bytes - seconds
+    pub min_principals: Vec<MinPrincipal>, // 4 + 40 * MAX_LOAN_MINTS bytes - per loan mint minimum
+    pub late_fee_policy: LateFeePolicy, // 14 bytes - default for collections without an override
+    pub compounding: Compounding,  // 5 bytes - interest compounding of new loans
+    pub auction_config: AuctionConfig, // 50 bytes - terms of liquidation auctions
     pub bump: u8,                  // 1 byte
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct MinPrincipal {
+    pub mint: Pubkey,              // 32 bytes
+    pub amount: u64,               // 8 bytes - base units of the loan mint
+}
+
+impl MinPrincipal {
+    pub const LEN: usize = 32 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LateFeePolicy {
+    pub grace_period: i64,         // 8 bytes - seconds after a due time before liquidation
+    pub flat_fee_bps: u16,         // 2 bytes - one-off fee on the overdue amount
+    pub daily_fee_bps: u16,        // 2 bytes - fee per started day overdue
+    pub treasury_share_bps: u16,   // 2 bytes - share of late fees paid to the treasury
+}
+
+impl LateFeePolicy {
+    pub const LEN: usize = 8 + 2 + 2 + 2;
+    
+    pub fn is_valid(&self) -> bool {
+        self.grace_period >= 0 && self.treasury_share_bps <= 10000
+    }
+    
+    // Late fee on `amount` due at `due_time`, zero until the due time passes
+    pub fn late_fee(&self, amount: u64, due_time: i64, current_time: i64) -> Result<u64> {
+        if current_time <= due_time {
+            return Ok(0);
+        }
+        let days_late = current_time.abs_diff(due_time).div_ceil(24 * 3600);
+        let fee_bps = (self.daily_fee_bps as u64)
+            .checked_mul(days_late)
+            .and_then(|fee| fee.checked_add(self.flat_fee_bps as u64))
+            .ok_or(LoanError::MathOverflow)?;
+        checked_mul_div(amount, fee_bps, 10000)
+    }
+    
+    pub fn treasury_share(&self, fee: u64) -> Result<u64> {
+        checked_mul_div(fee, self.treasury_share_bps as u64, 10000)
+    }
+}
+
 impl Protocol {
-    pub const LEN: usize = 32 + 32 + 2 + 8 + 8 + 1;
+    pub const MAX_PRICE_UPDATERS: usize = 8;
+    pub const MAX_LOAN_MINTS: usize = 8;
+    pub const LEN: usize = 32 + 32 + 2 + 8 + 8 + 1 + (4 + 32 * Self::MAX_PRICE_UPDATERS)
+        + 2 + 2 + 8 + 8 + (4 + MinPrincipal::LEN * Self::MAX_LOAN_MINTS) + LateFeePolicy::LEN + 5
+        + AuctionConfig::LEN + 1;
+    
+    // Loans in mints without a configured minimum only need a nonzero principal
+    pub fn min_principal(&self, loan_mint: &Pubkey) -> u64 {
+        self.min_principals
+            .iter()
+            .find(|min| min.mint == *loan_mint)
+            .map_or(1, |min| min.amount.max(1))
+    }
+    
+    pub fn is_price_updater(&self, key: &Pubkey) -> bool {
+        self.price_updaters.contains(key)
+    }
+    
+    pub fn token_standard_flag(token_standard: TokenStandard) -> u8 {
+        1 << token_standard as u8
+    }
+    
+    pub fn is_token_standard_allowed(&self, token_standard: TokenStandard) -> bool {
+        self.allowed_token_standards & Self::token_standard_flag(token_standard) != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    
+    const DAY: i64 = 24 * 3600;
+    
+    fn policy(flat_fee_bps: u16, daily_fee_bps: u16) -> LateFeePolicy {
+        LateFeePolicy {
+            grace_period: 3 * DAY,
+            flat_fee_bps,
+            daily_fee_bps,
+            treasury_share_bps: 2000,
+        }
+    }
+    
+    #[test]
+    fn late_fee_counts_started_days() {
+        let policy = policy(100, 10);
+        assert_eq!(policy.late_fee(1_000_000, 0, 0).unwrap(), 0);
+        assert_eq!(policy.late_fee(1_000_000, 0, 1).unwrap(), 11_000);
+        assert_eq!(policy.late_fee(1_000_000, 0, DAY + 1).unwrap(), 12_000);
+        assert_eq!(policy.treasury_share(12_000).unwrap(), 2_400);
+    }
+    
+    #[test]
+    fn late_fee_overflow_is_an_error() {
+        let policy = policy(u16::MAX, u16::MAX);
+        assert!(policy.late_fee(u64::MAX, i64::MIN, i64::MAX).is_err());
+        assert_eq!(policy.late_fee(0, i64::MIN, i64::MAX).unwrap(), 0);
+    }
+    
+    proptest! {
+        #[test]
+        fn late_fee_never_panics(
+            amount: u64,
+            flat_fee_bps: u16,
+            daily_fee_bps: u16,
+            due_time: i64,
+            current_time: i64,
+        ) {
+            let policy = policy(flat_fee_bps, daily_fee_bps);
+            if let Ok(fee) = policy.late_fee(amount, due_time, current_time) {
+                prop_assert!(policy.treasury_share(fee).unwrap() <= fee);
+            }
+        }
+    }
 }
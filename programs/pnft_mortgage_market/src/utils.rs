@@ -1,18 +1,251 @@
+use anchor_lang::prelude::*;
 
-pub fn calculate_health_ratio(collateral_value: u64, loan_amount: u64) -> u64 {
-    if loan_amount == 0 {
-        return 0;
-    }
-    (collateral_value * 10000) / loan_amount
+use crate::errors::*;
+use crate::interest::{mul_div, wad_pow, SECONDS_PER_YEAR, WAD};
+
+// a * b / denominator on token amounts, rounded down; errors instead of wrapping
+pub fn checked_mul_div(a: u64, b: u64, denominator: u64) -> Result<u64> {
+    let result = mul_div(a as u128, b as u128, denominator as u128)?;
+    u64::try_from(result).map_err(|_| LoanError::MathOverflow.into())
+}
+
+pub fn checked_add(a: u64, b: u64) -> Result<u64> {
+    a.checked_add(b).ok_or(LoanError::MathOverflow.into())
+}
+
+pub fn checked_sub(a: u64, b: u64) -> Result<u64> {
+    a.checked_sub(b).ok_or(LoanError::MathOverflow.into())
+}
+
+// Ratio of collateral_value to loan_amount in bps
+pub fn calculate_health_ratio(collateral_value: u64, loan_amount: u64) -> Result<u64> {
+    checked_mul_div(collateral_value, 10000, loan_amount)
 }
 
 pub fn is_healthy_ratio(health_ratio: u64, threshold: u64) -> bool {
     health_ratio >= threshold
 }
 
-pub fn calculate_liquidation_price(loan_amount: u64, collateral_amount: u64, threshold: u64) -> u64 {
-    if collateral_amount == 0 {
+pub fn calculate_liquidation_price(loan_amount: u64, collateral_amount: u64, threshold: u64) -> Result<u64> {
+    let denominator = (collateral_amount as u128)
+        .checked_mul(10000)
+        .ok_or(LoanError::MathOverflow)?;
+    let price = mul_div(loan_amount as u128, threshold as u128, denominator)?;
+    u64::try_from(price).map_err(|_| LoanError::MathOverflow.into())
+}
+
+pub fn calculate_fee(amount: u64, fee_rate: u16) -> Result<u64> {
+    checked_mul_div(amount, fee_rate as u64, 10000)
+}
+
+// Share of pool assets lent out, in basis points
+pub fn calculate_utilization(total_borrowed: u64, total_assets: u64) -> u64 {
+    if total_assets == 0 {
         return 0;
     }
-    (loan_amount * threshold) / (collateral_amount * 10000)
+    ((total_borrowed as u128 * 10000) / total_assets as u128).min(10000) as u64
+}
+
+// Kinked borrow rate in APR bps: base + slope1 up to the kink, then slope2 to 100% utilization
+pub fn calculate_borrow_rate(utilization: u64, base_rate: u16, slope1: u16, kink: u16, slope2: u16) -> u64 {
+    let utilization = utilization.min(10000);
+    let (base_rate, slope1, kink, slope2) = (base_rate as u64, slope1 as u64, kink as u64, slope2 as u64);
+    
+    if utilization <= kink {
+        if kink == 0 {
+            return base_rate;
+        }
+        base_rate + slope1 * utilization / kink
+    } else {
+        base_rate + slope1 + slope2 * (utilization - kink) / (10000 - kink)
+    }
+}
+
+// Level payment amortizing `principal` over `count` periods of `period` seconds:
+// P * r / (1 - (1 + r)^-n), with r the periodic rate in WAD fixed-point
+pub fn calculate_installment(principal: u64, interest_rate: u16, period: i64, count: u16) -> Option<u64> {
+    if period <= 0 || count == 0 {
+        return None;
+    }
+    let rate = (interest_rate as u128)
+        .checked_mul(period as u128)?
+        .checked_mul(WAD)?
+        / (10000 * SECONDS_PER_YEAR);
+    if rate == 0 {
+        return u64::try_from((principal as u128).div_ceil(count as u128)).ok();
+    }
+    
+    // Payment per unit of principal: r * (1 + r)^n / ((1 + r)^n - 1)
+    let growth = wad_pow(WAD + rate, count as u64).ok()?;
+    let factor = rate.checked_mul(growth)? / growth.checked_sub(WAD).filter(|d| *d > 0)?;
+    let installment = (principal as u128).checked_mul(factor)?.div_ceil(WAD);
+    
+    u64::try_from(installment).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    
+    // 2% base, +8% to an 80% kink, +100% above it
+    fn rate(utilization: u64) -> u64 {
+        calculate_borrow_rate(utilization, 200, 800, 8000, 10000)
+    }
+    
+    #[test]
+    fn utilization_is_borrowed_over_assets() {
+        assert_eq!(calculate_utilization(0, 0), 0);
+        assert_eq!(calculate_utilization(0, 1_000), 0);
+        assert_eq!(calculate_utilization(250, 1_000), 2500);
+        assert_eq!(calculate_utilization(1_000, 1_000), 10000);
+        assert_eq!(calculate_utilization(u64::MAX, u64::MAX), 10000);
+    }
+    
+    #[test]
+    fn borrow_rate_below_kink_follows_slope1() {
+        assert_eq!(rate(0), 200);
+        assert_eq!(rate(2000), 400);
+        assert_eq!(rate(4000), 600);
+        assert_eq!(rate(8000), 1000);
+    }
+    
+    #[test]
+    fn borrow_rate_above_kink_follows_slope2() {
+        assert_eq!(rate(9000), 6000);
+        assert_eq!(rate(9500), 8500);
+        assert_eq!(rate(10000), 11000);
+        assert_eq!(rate(20000), 11000);
+    }
+    
+    #[test]
+    fn borrow_rate_is_monotonic_and_continuous_at_kink() {
+        let mut previous = rate(0);
+        for utilization in (0..=10000).step_by(50) {
+            let current = rate(utilization);
+            assert!(current >= previous);
+            previous = current;
+        }
+        assert_eq!(rate(8000), 1000);
+        assert_eq!(rate(8001), 1005);
+    }
+    
+    #[test]
+    fn borrow_rate_handles_degenerate_kinks() {
+        // No kink: slope2 applies from 0% utilization
+        assert_eq!(calculate_borrow_rate(0, 100, 500, 0, 1000), 100);
+        assert_eq!(calculate_borrow_rate(5000, 100, 500, 0, 1000), 1100);
+        // Kink at 100%: slope1 only
+        assert_eq!(calculate_borrow_rate(5000, 100, 500, 10000, 1000), 350);
+        assert_eq!(calculate_borrow_rate(10000, 100, 500, 10000, 1000), 600);
+    }
+    
+    #[test]
+    fn zero_denominators_are_rejected() {
+        let division_by_zero: Error = LoanError::DivisionByZero.into();
+        assert_eq!(checked_mul_div(1, 1, 0).unwrap_err(), division_by_zero);
+        assert_eq!(calculate_health_ratio(1_000, 0).unwrap_err(), division_by_zero);
+        assert_eq!(calculate_liquidation_price(1_000, 0, 8000).unwrap_err(), division_by_zero);
+    }
+    
+    #[test]
+    fn results_past_u64_are_rejected() {
+        let overflow: Error = LoanError::MathOverflow.into();
+        assert_eq!(checked_mul_div(u64::MAX, 2, 1).unwrap_err(), overflow);
+        assert_eq!(checked_add(u64::MAX, 1).unwrap_err(), overflow);
+        assert_eq!(checked_sub(0, 1).unwrap_err(), overflow);
+        assert_eq!(calculate_health_ratio(u64::MAX, 1).unwrap_err(), overflow);
+        assert_eq!(calculate_fee(u64::MAX, u16::MAX).unwrap_err(), overflow);
+        assert_eq!(calculate_liquidation_price(u64::MAX, 1, u64::MAX).unwrap_err(), overflow);
+    }
+    
+    #[test]
+    fn boundary_values_that_fit_are_exact() {
+        assert_eq!(checked_mul_div(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+        assert_eq!(calculate_health_ratio(u64::MAX, u64::MAX).unwrap(), 10000);
+        assert_eq!(calculate_health_ratio(0, 1).unwrap(), 0);
+        assert_eq!(calculate_fee(u64::MAX, 10000).unwrap(), u64::MAX);
+        assert_eq!(calculate_liquidation_price(u64::MAX, u64::MAX, 10000).unwrap(), 1);
+    }
+    
+    // P * r / (1 - (1 + r)^-n) in floating point
+    fn closed_form_installment(principal: u64, interest_rate: u16, period: i64, count: u16) -> f64 {
+        let rate = interest_rate as f64 / 10000.0 * period as f64 / SECONDS_PER_YEAR as f64;
+        principal as f64 * rate / (1.0 - (1.0 + rate).powi(-(count as i32)))
+    }
+    
+    #[test]
+    fn installment_matches_the_closed_form() {
+        const DAY: i64 = 24 * 3600;
+        for (principal, interest_rate, period, count) in [
+            (1_000_000_000, 1200, 30 * DAY, 12),
+            (5_000_000, 500, 7 * DAY, 52),
+            (250_000_000_000, 3650, DAY, 365),
+            (1_000_000, 10000, 365 * DAY, 3),
+        ] {
+            let installment = calculate_installment(principal, interest_rate, period, count).unwrap();
+            let expected = closed_form_installment(principal, interest_rate, period, count);
+            assert!(
+                (installment as f64 - expected).abs() <= 2.0,
+                "installment {installment} expected {expected}"
+            );
+            // Rounded up, so n installments always cover the principal
+            assert!(installment as u128 * count as u128 >= principal as u128);
+        }
+        // A single period is the principal plus one period of interest
+        assert_eq!(calculate_installment(1_000_000, 10000, 365 * 24 * 3600, 1), Some(2_000_000));
+    }
+    
+    #[test]
+    fn zero_rate_installments_split_the_principal() {
+        assert_eq!(calculate_installment(900, 0, 3600, 3), Some(300));
+        // Rounded up so the last installment is never short
+        assert_eq!(calculate_installment(1_000, 0, 3600, 3), Some(334));
+        assert_eq!(calculate_installment(1, 0, 3600, 10), Some(1));
+        assert_eq!(calculate_installment(0, 0, 3600, 10), Some(0));
+    }
+    
+    #[test]
+    fn degenerate_schedules_have_no_installment() {
+        assert_eq!(calculate_installment(1_000, 500, 0, 12), None);
+        assert_eq!(calculate_installment(1_000, 500, -1, 12), None);
+        assert_eq!(calculate_installment(1_000, 500, 3600, 0), None);
+        assert_eq!(calculate_installment(u64::MAX, 10000, 365 * 24 * 3600, 1), None);
+    }
+    
+    proptest! {
+        #[test]
+        fn checked_mul_div_matches_u128_reference(a: u64, b: u64, denominator in 1u64..) {
+            let reference = a as u128 * b as u128 / denominator as u128;
+            match checked_mul_div(a, b, denominator) {
+                Ok(result) => prop_assert_eq!(result as u128, reference),
+                Err(_) => prop_assert!(reference > u64::MAX as u128),
+            }
+        }
+        
+        #[test]
+        fn health_ratio_never_wraps(collateral_value: u64, loan_amount in 1u64..) {
+            let reference = collateral_value as u128 * 10000 / loan_amount as u128;
+            match calculate_health_ratio(collateral_value, loan_amount) {
+                Ok(ratio) => prop_assert_eq!(ratio as u128, reference),
+                Err(_) => prop_assert!(reference > u64::MAX as u128),
+            }
+        }
+        
+        #[test]
+        fn fee_never_exceeds_amount(amount: u64, fee_rate in 0u16..=10000) {
+            let fee = calculate_fee(amount, fee_rate).unwrap();
+            prop_assert!(fee <= amount);
+            prop_assert!(checked_sub(amount, fee).is_ok());
+        }
+        
+        #[test]
+        fn liquidation_price_never_wraps(loan_amount: u64, collateral_amount in 1u64.., threshold: u64) {
+            let reference = loan_amount as u128 * threshold as u128 / (collateral_amount as u128 * 10000);
+            match calculate_liquidation_price(loan_amount, collateral_amount, threshold) {
+                Ok(price) => prop_assert_eq!(price as u128, reference),
+                Err(_) => prop_assert!(reference > u64::MAX as u128),
+            }
+        }
+    }
 }